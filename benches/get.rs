@@ -0,0 +1,58 @@
+//! Compares `Metadata::get` on 5000 packages without and with the package index.
+//!
+//! Run with `cargo bench --bench get`.
+
+use std::hint::black_box;
+use std::io::{self, Write as _};
+use std::time::{Duration, Instant};
+
+use wesl_metadata::{Metadata, MetadataCommand, PackageId};
+
+const PACKAGES: usize = 5000;
+
+fn fixture() -> String {
+	let packages: Vec<String> = (0..PACKAGES)
+		.map(|index| {
+			format!(
+				r#"{{"name": "package-{index}", "version": "1.0.0", "id": "package-{index}@1.0.0", "source": null, "dependencies": [], "manifest_path": "/package-{index}/wesl.toml"}}"#
+			)
+		})
+		.collect();
+	format!(
+		r#"{{"package_manager": "Cargo", "packages": [{}], "target_directory": "/target", "version": 1, "root_package_directory": "/"}}"#,
+		packages.join(", ")
+	)
+}
+
+/// The time taken to look up every package of `metadata` by id.
+fn lookup_all(metadata: &Metadata) -> Duration {
+	let ids: Vec<PackageId> = metadata
+		.packages
+		.iter()
+		.map(|package| package.id.clone())
+		.collect();
+	let start = Instant::now();
+	for id in &ids {
+		black_box(metadata.get(black_box(id)));
+	}
+	start.elapsed()
+}
+
+fn main() -> io::Result<()> {
+	let json = fixture();
+	// deserializing directly does not build the index, so every lookup scans
+	let scanned: Metadata = serde_json::from_str(&json)?;
+	let indexed = MetadataCommand::parse(&json).map_err(io::Error::other)?;
+
+	let mut stdout = io::stdout().lock();
+	writeln!(
+		stdout,
+		"{PACKAGES} lookups without index: {} us",
+		lookup_all(&scanned).as_micros()
+	)?;
+	writeln!(
+		stdout,
+		"{PACKAGES} lookups with index: {} us",
+		lookup_all(&indexed).as_micros()
+	)
+}
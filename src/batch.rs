@@ -0,0 +1,122 @@
+//! This module contains [`MetadataCommand::exec_many`].
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+
+use crate::{Metadata, MetadataCommand, Result};
+
+impl MetadataCommand {
+	/// Runs configured `wesl metadata` once for each of `manifests`, running up to
+	/// `parallelism` processes at the same time.
+	///
+	/// Each run uses a copy of this command with [`Self::manifest_path`] set to the manifest.
+	/// The results are returned in the order of `manifests`, and a failing manifest does not
+	/// stop the others. A `parallelism` of zero is treated as one.
+	pub fn exec_many<Manifests: IntoIterator<Item = PathBuf>>(
+		&self,
+		manifests: Manifests,
+		parallelism: usize,
+	) -> Vec<(PathBuf, Result<Metadata>)> {
+		let manifests: Vec<PathBuf> = manifests.into_iter().collect();
+		let next = AtomicUsize::new(0);
+		let results = Mutex::new(BTreeMap::new());
+		thread::scope(|scope| {
+			// each worker runs one process at a time, so there are never more than `parallelism`
+			for _ in 0..parallelism.clamp(1, manifests.len().max(1)) {
+				scope.spawn(|| {
+					loop {
+						let position = next.fetch_add(1, Ordering::Relaxed);
+						let Some(manifest) = manifests.get(position) else {
+							break;
+						};
+						let result = self.clone().manifest_path(manifest).exec();
+						results
+							.lock()
+							.unwrap_or_else(PoisonError::into_inner)
+							.insert(position, result);
+					}
+				});
+			}
+		});
+		manifests
+			.into_iter()
+			.zip(
+				results
+					.into_inner()
+					.unwrap_or_else(PoisonError::into_inner)
+					.into_values(),
+			)
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	#[cfg(unix)]
+	use std::fs;
+
+	#[cfg(unix)]
+	use super::*;
+	#[cfg(unix)]
+	use crate::Error;
+	#[cfg(unix)]
+	use crate::tests::TempDir;
+
+	#[cfg(unix)]
+	#[test]
+	fn exec_many() {
+		let directory = TempDir::new("exec-many");
+		let running = directory.path().join("running");
+		let log = directory.path().join("exec-many.log");
+		fs::create_dir_all(&running).unwrap();
+		let wesl = directory.shim(
+			"exec-many",
+			&format!(
+				r#"touch '{running}/'$$
+ls '{running}' | wc -l >> '{log}'
+sleep 0.2
+rm '{running}/'$$
+case "$3" in *bad*) echo 'bad manifest' >&2; exit 1;; esac
+printf '{{"package_manager": "Cargo", "packages": [], "target_directory": "/target", "version": 1, "root_package_directory": "%s"}}' "$3""#,
+			),
+		);
+		let manifests: Vec<PathBuf> = ["first", "bad", "third", "fourth"]
+			.iter()
+			.map(|name| {
+				directory
+					.write(&format!("exec-many-{name}.toml"), "")
+					.into_std_path_buf()
+			})
+			.collect();
+
+		let results = MetadataCommand::new()
+			.wesl_path(wesl)
+			.exec_many(manifests.clone(), 2);
+		let paths: Vec<&PathBuf> = results.iter().map(|(path, _)| path).collect();
+		assert_eq!(paths, manifests.iter().collect::<Vec<_>>());
+		for (manifest, result) in &results {
+			if manifest.to_str().unwrap().contains("bad") {
+				assert!(matches!(result, Err(Error::WeslMetadata { .. })));
+			} else {
+				assert_eq!(
+					result.as_ref().unwrap().root_package_directory,
+					manifest.to_str().unwrap()
+				);
+			}
+		}
+
+		let concurrency: Vec<usize> = fs::read_to_string(&log)
+			.unwrap()
+			.lines()
+			.map(|line| line.trim().parse().unwrap())
+			.collect();
+		assert_eq!(concurrency.len(), 4);
+		assert!(
+			concurrency.iter().all(|running| *running <= 2),
+			"{concurrency:?}"
+		);
+	}
+}
@@ -0,0 +1,254 @@
+//! This module contains [`BuildError`] and the conversions from builders that return it.
+
+use crate::{Metadata, MetadataBuilder, Node, NodeBuilder, Package, PackageBuilder};
+
+/// Error returned when converting a builder into the value it builds fails.
+///
+/// Unlike the error of `build`, this lists every missing field at once.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum BuildError {
+	/// Required fields were not set.
+	#[error("missing required fields of `{type_name}`: {}", fields.join(", "))]
+	MissingFields {
+		/// The type being built.
+		type_name: &'static str,
+		/// The names of the fields that were not set.
+		fields: Vec<&'static str>,
+	},
+
+	/// A field was set to an invalid value.
+	#[error("invalid `{type_name}`: {message}")]
+	Invalid {
+		/// The type being built.
+		type_name: &'static str,
+		/// What is wrong with the value.
+		message: String,
+	},
+}
+
+/// Check that every required field is set, reporting all of those that are not.
+pub(crate) fn require(
+	type_name: &'static str,
+	fields: &[(&'static str, bool)],
+) -> Result<(), BuildError> {
+	let missing: Vec<&'static str> = fields
+		.iter()
+		.filter(|(_, set)| !set)
+		.map(|(field, _)| *field)
+		.collect();
+	if missing.is_empty() {
+		Ok(())
+	} else {
+		Err(BuildError::MissingFields {
+			type_name,
+			fields: missing,
+		})
+	}
+}
+
+/// Build the error for a builder whose required fields are all set, which can only fail validation.
+pub(crate) fn invalid<Error: ToString>(
+	type_name: &'static str
+) -> impl FnOnce(Error) -> BuildError {
+	move |error| BuildError::Invalid {
+		type_name,
+		message: error.to_string(),
+	}
+}
+
+impl TryFrom<MetadataBuilder> for Metadata {
+	type Error = BuildError;
+
+	fn try_from(builder: MetadataBuilder) -> Result<Self, Self::Error> {
+		require(
+			"Metadata",
+			&[
+				("package_manager", builder.package_manager.is_some()),
+				("packages", builder.packages.is_some()),
+				("resolve", builder.resolve.is_some()),
+				("target_directory", builder.target_directory.is_some()),
+				("version", builder.version.is_some()),
+				(
+					"root_package_directory",
+					builder.root_package_directory.is_some(),
+				),
+			],
+		)?;
+		builder.build().map_err(invalid("Metadata"))
+	}
+}
+
+impl PackageBuilder {
+	/// Reject an empty name or id, and a manifest path without a file name.
+	pub(crate) fn validate(&self) -> Result<(), String> {
+		if self.name.as_ref().is_some_and(String::is_empty) {
+			return Err("the name is empty".to_owned());
+		}
+		if self.id.as_ref().is_some_and(|id| id.repr.is_empty()) {
+			return Err("the id is empty".to_owned());
+		}
+		if let Some(manifest_path) = &self.manifest_path
+			&& manifest_path.file_name().is_none()
+		{
+			return Err(format!(
+				"the manifest path `{manifest_path}` has no file name"
+			));
+		}
+		Ok(())
+	}
+}
+
+impl TryFrom<PackageBuilder> for Package {
+	type Error = BuildError;
+
+	fn try_from(builder: PackageBuilder) -> Result<Self, Self::Error> {
+		require(
+			"Package",
+			&[
+				("name", builder.name.is_some()),
+				("version", builder.version.is_some()),
+				("id", builder.id.is_some()),
+				("source", builder.source.is_some()),
+				("manifest_path", builder.manifest_path.is_some()),
+			],
+		)?;
+		builder.build().map_err(invalid("Package"))
+	}
+}
+
+impl NodeBuilder {
+	/// Reject an empty id.
+	pub(crate) fn validate(&self) -> Result<(), String> {
+		if self.id.as_ref().is_some_and(|id| id.repr.is_empty()) {
+			return Err("the id is empty".to_owned());
+		}
+		Ok(())
+	}
+}
+
+impl TryFrom<NodeBuilder> for Node {
+	type Error = BuildError;
+
+	fn try_from(builder: NodeBuilder) -> Result<Self, Self::Error> {
+		require(
+			"Node",
+			&[
+				("id", builder.id.is_some()),
+				(
+					"renamed_dependencies",
+					builder.renamed_dependencies.is_some(),
+				),
+				("dependencies", builder.dependencies.is_some()),
+			],
+		)?;
+		builder.build().map_err(invalid("Node"))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{DependencyBuilder, PackageId};
+
+	fn id(repr: &str) -> PackageId {
+		PackageId {
+			repr: repr.to_owned(),
+		}
+	}
+
+	#[test]
+	fn package() {
+		let package = Package::try_from(
+			PackageBuilder::new(
+				"app",
+				semver::Version::new(1, 0, 0),
+				id("app@1.0.0"),
+				"/app/wesl.toml",
+			)
+			.source(None),
+		)
+		.unwrap();
+		assert_eq!(package.name, "app");
+
+		let error = Package::try_from(PackageBuilder::default().name("app")).unwrap_err();
+		assert_eq!(
+			error.to_string(),
+			"missing required fields of `Package`: version, id, source, manifest_path"
+		);
+
+		for (builder, message) in [
+			(
+				PackageBuilder::new(
+					"",
+					semver::Version::new(1, 0, 0),
+					id("app@1.0.0"),
+					"/app/wesl.toml",
+				),
+				"invalid `Package`: the name is empty",
+			),
+			(
+				PackageBuilder::new(
+					"app",
+					semver::Version::new(1, 0, 0),
+					id(""),
+					"/app/wesl.toml",
+				),
+				"invalid `Package`: the id is empty",
+			),
+			(
+				PackageBuilder::new("app", semver::Version::new(1, 0, 0), id("app@1.0.0"), "/"),
+				"invalid `Package`: the manifest path `/` has no file name",
+			),
+		] {
+			let error = Package::try_from(builder.source(None)).unwrap_err();
+			assert_eq!(error.to_string(), message);
+		}
+	}
+
+	#[test]
+	fn others() {
+		let error = Metadata::try_from(MetadataBuilder::default().version(1_usize)).unwrap_err();
+		assert_eq!(
+			error,
+			BuildError::MissingFields {
+				type_name: "Metadata",
+				fields: vec![
+					"package_manager",
+					"packages",
+					"resolve",
+					"target_directory",
+					"root_package_directory"
+				],
+			}
+		);
+
+		let error = Node::try_from(NodeBuilder::default().id(id(""))).unwrap_err();
+		assert_eq!(
+			error.to_string(),
+			"missing required fields of `Node`: renamed_dependencies, dependencies"
+		);
+		let error = Node::try_from(
+			NodeBuilder::default()
+				.id(id(""))
+				.renamed_dependencies(Vec::new())
+				.dependencies(Vec::new()),
+		)
+		.unwrap_err();
+		assert_eq!(error.to_string(), "invalid `Node`: the id is empty");
+
+		let error = crate::Dependency::try_from(DependencyBuilder::default().name("")).unwrap_err();
+		assert_eq!(
+			error.to_string(),
+			"missing required fields of `Dependency`: rename, path"
+		);
+		let error = crate::Dependency::try_from(
+			DependencyBuilder::default()
+				.name("")
+				.rename(None)
+				.path(None),
+		)
+		.unwrap_err();
+		assert_eq!(error.to_string(), "invalid `Dependency`: the name is empty");
+	}
+}
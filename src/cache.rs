@@ -0,0 +1,127 @@
+//! This module contains [`MetadataCommand::exec_or_cached`].
+
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crate::{Metadata, MetadataCommand, Result};
+
+impl MetadataCommand {
+	/// Reads the metadata cached at `cache_path` if it was written less than `max_age` ago,
+	/// otherwise runs configured `wesl metadata` and caches its result there.
+	///
+	/// The age is taken from the modification time of the cache file. A cache that cannot be
+	/// read or parsed is replaced. The cache is written to a temporary file next to
+	/// `cache_path`, unique to this process and call, and then renamed, so readers never see
+	/// a partially written file. Failing to write the cache does not fail this call.
+	///
+	/// The cache is not keyed by the configuration of this command: a cache written by a
+	/// command with other settings, e.g. another manifest path or features, is returned as
+	/// long as it is young enough. Use a separate `cache_path` for each configuration.
+	pub fn exec_or_cached(
+		&self,
+		cache_path: &Path,
+		max_age: Duration,
+	) -> Result<Metadata> {
+		if let Some(metadata) = read_cache(cache_path, max_age) {
+			return Ok(metadata);
+		}
+		let metadata = self.exec()?;
+		write_cache(cache_path, &metadata);
+		Ok(metadata)
+	}
+}
+
+/// The metadata cached at `path`, if it is younger than `max_age` and valid.
+fn read_cache(
+	path: &Path,
+	max_age: Duration,
+) -> Option<Metadata> {
+	let age = fs::metadata(path).ok()?.modified().ok()?.elapsed().ok()?;
+	if age >= max_age {
+		return None;
+	}
+	Metadata::try_from(fs::read(path).ok()?.as_slice()).ok()
+}
+
+/// Write `metadata` to `path` through a temporary file, ignoring any failure.
+fn write_cache(
+	path: &Path,
+	metadata: &Metadata,
+) {
+	static WRITES: AtomicUsize = AtomicUsize::new(0);
+	let Ok(json) = metadata.to_json() else {
+		return;
+	};
+	let mut temporary = OsString::from(path);
+	temporary.push(format!(
+		".{}.{}.tmp",
+		process::id(),
+		WRITES.fetch_add(1, Ordering::Relaxed)
+	));
+	let temporary = PathBuf::from(temporary);
+	let written = fs::write(&temporary, json).and_then(|()| fs::rename(&temporary, path));
+	if written.is_err() && temporary.exists() {
+		// the cache is best effort, a leftover temporary file is only untidy
+		drop(fs::remove_file(&temporary));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	#[cfg(unix)]
+	use super::*;
+	#[cfg(unix)]
+	use crate::tests::TempDir;
+
+	#[cfg(unix)]
+	#[test]
+	fn exec_or_cached() {
+		let directory = TempDir::new("exec-or-cached");
+		let runs = directory.path().join("exec-or-cached.runs");
+		let cache = directory.path().as_std_path().join("exec-or-cached.json");
+		let wesl = directory.shim(
+			"exec-or-cached",
+			&format!(
+				r#"echo run >> '{runs}'
+echo '{{"package_manager": "Cargo", "packages": [], "target_directory": "/target", "version": 1, "root_package_directory": "/"}}'"#,
+			),
+		);
+		let mut command = MetadataCommand::new();
+		command.wesl_path(wesl);
+		let run_count = || fs::read_to_string(&runs).unwrap().lines().count();
+		let hour = Duration::from_hours(1);
+
+		let metadata = command.exec_or_cached(&cache, hour).unwrap();
+		assert_eq!(metadata.target_directory, "/target");
+		assert_eq!(run_count(), 1);
+		assert_eq!(command.exec_or_cached(&cache, hour).unwrap(), metadata);
+		assert_eq!(run_count(), 1);
+		let temporaries = fs::read_dir(cache.parent().unwrap())
+			.unwrap()
+			.filter(|entry| {
+				let name = entry.as_ref().unwrap().file_name();
+				name.to_string_lossy().starts_with("exec-or-cached.json.")
+			})
+			.count();
+		assert_eq!(temporaries, 0);
+
+		assert_eq!(
+			command.exec_or_cached(&cache, Duration::ZERO).unwrap(),
+			metadata
+		);
+		assert_eq!(run_count(), 2);
+
+		fs::write(&cache, "{").unwrap();
+		assert_eq!(command.exec_or_cached(&cache, hour).unwrap(), metadata);
+		assert_eq!(run_count(), 3);
+		fs::remove_file(&cache).unwrap();
+
+		let unwritable = cache.join("missing").join("cache.json");
+		assert_eq!(command.exec_or_cached(&unwritable, hour).unwrap(), metadata);
+		assert_eq!(run_count(), 4);
+	}
+}
@@ -3,15 +3,24 @@
 use camino::Utf8PathBuf;
 #[cfg(feature = "builder")]
 use derive_builder::Builder;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+
+#[cfg(feature = "builder")]
+use crate::BuildError;
+#[cfg(feature = "builder")]
+use crate::builder::{invalid, require};
+use crate::default_true;
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "builder", derive(Builder))]
 #[non_exhaustive]
-#[cfg_attr(feature = "builder", builder(pattern = "owned", setter(into)))]
-/// A dependency of the main crate
+#[cfg_attr(
+	feature = "builder",
+	builder(pattern = "owned", setter(into), build_fn(validate = "Self::validate"))
+)]
+/// A dependency of the main crate.
 pub struct Dependency {
-	/// Name as given in the `wesl.toml`
+	/// Name as given in the `wesl.toml`.
 	pub name: String,
 
 	/// If the dependency is renamed, this is the new name for the dependency
@@ -20,4 +29,267 @@ pub struct Dependency {
 
 	/// The file system path for a local path dependency.
 	pub path: Option<Utf8PathBuf>,
+
+	/// The git repository URL for a git dependency.
+	#[serde(default)]
+	#[cfg_attr(feature = "builder", builder(default))]
+	pub git: Option<String>,
+
+	/// Whether this is a normal, dev or build dependency. `null` means a normal dependency.
+	#[serde(default, deserialize_with = "null_as_normal")]
+	#[cfg_attr(feature = "builder", builder(default))]
+	pub kind: DependencyKind,
+
+	/// Whether this dependency is only enabled by a feature.
+	#[serde(default)]
+	#[cfg_attr(feature = "builder", builder(default))]
+	pub optional: bool,
+
+	/// Whether the default features of this dependency are enabled.
+	#[serde(default = "default_true")]
+	#[cfg_attr(feature = "builder", builder(default = "true"))]
+	pub uses_default_features: bool,
+
+	/// The features enabled on this dependency.
+	#[serde(default)]
+	#[cfg_attr(feature = "builder", builder(default))]
+	pub features: Vec<String>,
+}
+
+/// When a [`Dependency`] is used, see [`Dependency::kind`].
+#[derive(
+	Clone, Copy, Default, Serialize, Deserialize, Debug, PartialEq, Eq, Hash, PartialOrd, Ord,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+	/// A dependency of the package itself.
+	#[default]
+	Normal,
+	/// A dependency only used by tests, examples and benchmarks.
+	Dev,
+	/// A dependency only used by build scripts.
+	Build,
+}
+
+/// Deserialize a [`DependencyKind`], treating `null` as [`DependencyKind::Normal`].
+fn null_as_normal<'de, De: Deserializer<'de>>(
+	deserializer: De
+) -> Result<DependencyKind, De::Error> {
+	Option::deserialize(deserializer).map(Option::unwrap_or_default)
+}
+
+/// Where a [`Dependency`] comes from, see [`Dependency::source_kind`].
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DependencySourceKind {
+	/// A local path, `path` is set.
+	Path,
+	/// A git repository, `git` is set.
+	Git,
+	/// A registry, neither `path` nor `git` is set.
+	Registry,
+}
+
+impl Dependency {
+	/// The name this dependency is imported under: `rename` if present, else `name`.
+	#[must_use]
+	pub fn effective_name(&self) -> &str {
+		self.rename.as_deref().unwrap_or(&self.name)
+	}
+
+	/// Returns true if this dependency is imported under another name than its package name.
+	#[must_use]
+	pub const fn is_renamed(&self) -> bool {
+		self.rename.is_some()
+	}
+
+	/// Returns true if this is a normal dependency.
+	#[must_use]
+	pub const fn is_normal(&self) -> bool {
+		matches!(self.kind, DependencyKind::Normal)
+	}
+
+	/// Returns true if this is a dev dependency.
+	#[must_use]
+	pub const fn is_dev(&self) -> bool {
+		matches!(self.kind, DependencyKind::Dev)
+	}
+
+	/// Returns true if this is a build dependency.
+	#[must_use]
+	pub const fn is_build(&self) -> bool {
+		matches!(self.kind, DependencyKind::Build)
+	}
+
+	/// Returns true if this dependency comes from a local path.
+	#[must_use]
+	pub const fn is_path(&self) -> bool {
+		matches!(self.source_kind(), DependencySourceKind::Path)
+	}
+
+	/// Returns true if this dependency comes from a git repository.
+	#[must_use]
+	pub const fn is_git(&self) -> bool {
+		matches!(self.source_kind(), DependencySourceKind::Git)
+	}
+
+	/// Returns true if this dependency comes from a registry.
+	#[must_use]
+	pub const fn is_registry(&self) -> bool {
+		matches!(self.source_kind(), DependencySourceKind::Registry)
+	}
+
+	/// Where this dependency comes from. A `path` takes precedence over a `git` URL.
+	#[must_use]
+	pub const fn source_kind(&self) -> DependencySourceKind {
+		if self.path.is_some() {
+			DependencySourceKind::Path
+		} else if self.git.is_some() {
+			DependencySourceKind::Git
+		} else {
+			DependencySourceKind::Registry
+		}
+	}
+
+	/// Returns true if both dependencies are imported under the same name,
+	/// regardless of their package name or path.
+	#[must_use]
+	pub fn same_import(
+		&self,
+		other: &Self,
+	) -> bool {
+		self.effective_name() == other.effective_name()
+	}
+}
+
+#[cfg(feature = "builder")]
+impl DependencyBuilder {
+	/// Reject an empty name.
+	fn validate(&self) -> Result<(), String> {
+		if self.name.as_ref().is_some_and(String::is_empty) {
+			return Err("the name is empty".to_owned());
+		}
+		Ok(())
+	}
+}
+
+#[cfg(feature = "builder")]
+impl TryFrom<DependencyBuilder> for Dependency {
+	type Error = BuildError;
+
+	fn try_from(builder: DependencyBuilder) -> Result<Self, Self::Error> {
+		require(
+			"Dependency",
+			&[
+				("name", builder.name.is_some()),
+				("rename", builder.rename.is_some()),
+				("path", builder.path.is_some()),
+			],
+		)?;
+		builder.build().map_err(invalid("Dependency"))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn dependency(
+		name: &str,
+		rename: Option<&str>,
+		path: Option<&str>,
+	) -> Dependency {
+		Dependency {
+			name: name.to_owned(),
+			rename: rename.map(str::to_owned),
+			path: path.map(Utf8PathBuf::from),
+			git: None,
+			kind: DependencyKind::Normal,
+			optional: false,
+			uses_default_features: true,
+			features: Vec::new(),
+		}
+	}
+
+	#[test]
+	fn effective_name() {
+		assert_eq!(dependency("noise", None, None).effective_name(), "noise");
+		assert_eq!(
+			dependency("noise", Some("perlin"), None).effective_name(),
+			"perlin"
+		);
+	}
+
+	#[test]
+	fn is_renamed() {
+		assert!(!dependency("noise", None, None).is_renamed());
+		assert!(dependency("noise", Some("perlin"), None).is_renamed());
+	}
+
+	#[test]
+	fn source_kind() {
+		let mut git = dependency("noise", None, None);
+		git.git = Some("https://example.com/noise.git".to_owned());
+		for (dependency, kind) in [
+			(
+				dependency("noise", None, Some("../noise")),
+				DependencySourceKind::Path,
+			),
+			(git, DependencySourceKind::Git),
+			(
+				dependency("noise", None, None),
+				DependencySourceKind::Registry,
+			),
+		] {
+			assert_eq!(dependency.source_kind(), kind);
+			assert_eq!(
+				[
+					dependency.is_path(),
+					dependency.is_git(),
+					dependency.is_registry()
+				],
+				[
+					kind == DependencySourceKind::Path,
+					kind == DependencySourceKind::Git,
+					kind == DependencySourceKind::Registry
+				]
+			);
+		}
+	}
+
+	#[test]
+	fn kind() {
+		for (json, kind) in [
+			(r#""kind": null,"#, DependencyKind::Normal),
+			("", DependencyKind::Normal),
+			(r#""kind": "normal","#, DependencyKind::Normal),
+			(r#""kind": "dev","#, DependencyKind::Dev),
+			(r#""kind": "build","#, DependencyKind::Build),
+		] {
+			let dependency: Dependency = serde_json::from_str(&format!(
+				r#"{{{json} "name": "noise", "rename": null, "path": null}}"#
+			))
+			.unwrap();
+			assert_eq!(dependency.kind, kind);
+			assert_eq!(
+				[
+					dependency.is_normal(),
+					dependency.is_dev(),
+					dependency.is_build()
+				],
+				[
+					kind == DependencyKind::Normal,
+					kind == DependencyKind::Dev,
+					kind == DependencyKind::Build
+				]
+			);
+		}
+	}
+
+	#[test]
+	fn same_import() {
+		let renamed = dependency("noise-v2", Some("noise"), Some("../noise-v2"));
+		let plain = dependency("noise", None, Some("../noise"));
+		assert!(renamed.same_import(&plain));
+		assert!(!renamed.same_import(&dependency("noise-v2", None, None)));
+	}
 }
@@ -1,6 +1,6 @@
-use std::{io, str::Utf8Error, string::FromUtf8Error};
+use std::{fmt, io, path::PathBuf, str::Utf8Error, string::FromUtf8Error};
 
-/// Custom result type for `wesl_metadata::Error`
+/// Custom result type for `wesl_metadata::Error`.
 pub type Result<T, E = Error> = ::std::result::Result<T, E>;
 
 /// Error returned when executing/parsing `wesl metadata` fails.
@@ -21,31 +21,206 @@ pub type Result<T, E = Error> = ::std::result::Result<T, E>;
 ///    really want to. (Either through `foreign_links` or by making it a field
 ///    value of a `ErrorKind` variant).
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum Error {
-	/// Error during execution of `wesl metadata`
-	#[error("`wesl metadata` exited with an error: {stderr}")]
+	/// Error during execution of `wesl metadata`.
+	#[error("`wesl metadata` exited with {}: {stderr}", ExitCode(*exit_code))]
+	#[non_exhaustive]
 	WeslMetadata {
-		/// stderr returned by the `wesl metadata` command
+		/// The exit code of the `wesl metadata` command, `None` if it was terminated by a signal.
+		exit_code: Option<i32>,
+		/// stderr returned by the `wesl metadata` command.
 		stderr: String,
+		/// stdout returned by the `wesl metadata` command, which may contain partial json.
+		stdout: String,
 	},
 
-	/// IO Error during execution of `wesl metadata`
+	/// The `wesl` executable could not be found.
+	#[error("could not find `wesl` at `{}`, is it installed?", attempted.display())]
+	#[non_exhaustive]
+	WeslNotFound {
+		/// The path of the `wesl` executable that was tried,
+		/// from `MetadataCommand::wesl_path`, `$WESL`, or the default `wesl`.
+		attempted: PathBuf,
+		/// The paths that were checked for the executable: `attempted` itself if it contains
+		/// a separator, or else `attempted` in each directory of `PATH`. On Windows, these are
+		/// tried with `.exe`, `.cmd` and `.bat` appended first.
+		candidates: Vec<PathBuf>,
+	},
+
+	/// `wesl --version` failed or did not print a version, see `MetadataCommand::wesl_version`.
+	#[error("could not find the version of `wesl` in: {output}")]
+	#[non_exhaustive]
+	WeslVersion {
+		/// stdout returned by `wesl --version`, or stderr if it failed.
+		output: String,
+	},
+
+	/// `wesl metadata` rejected a flag added by a typed setter of `MetadataCommand`,
+	/// such as `--locked`. Retrying without that setting may succeed.
+	#[error("`wesl metadata` does not support `{flag}`: {stderr}")]
+	#[non_exhaustive]
+	UnsupportedFlag {
+		/// The rejected flag, e.g. `--locked`.
+		flag: String,
+		/// The exit code of the `wesl metadata` command, `None` if it was terminated by a signal.
+		exit_code: Option<i32>,
+		/// stderr returned by the `wesl metadata` command.
+		stderr: String,
+		/// stdout returned by the `wesl metadata` command.
+		stdout: String,
+	},
+
+	/// The configured manifest path does not point to a file.
+	#[error("manifest `{}` does not exist", path.display())]
+	#[non_exhaustive]
+	ManifestNotFound {
+		/// The manifest path that was checked, joined to the current directory if one was set.
+		path: PathBuf,
+	},
+
+	/// IO Error during execution of `wesl metadata`.
 	#[error("failed to start `wesl metadata`: {0}")]
 	Io(#[from] io::Error),
 
-	/// Output of `wesl metadata` was not valid utf8
+	/// Output of `wesl metadata` was not valid utf8.
 	#[error("cannot convert the stdout of `wesl metadata`: {0}")]
 	Utf8(#[from] Utf8Error),
 
-	/// Error output of `wesl metadata` was not valid utf8
+	/// Error output of `wesl metadata` was not valid utf8.
 	#[error("cannot convert the stderr of `wesl metadata`: {0}")]
 	ErrUtf8(#[from] FromUtf8Error),
 
-	/// Deserialization error (structure of json did not match expected structure)
+	/// Deserialization error (structure of json did not match expected structure).
 	#[error("failed to interpret `wesl metadata`'s json: {0}")]
 	Json(#[from] ::serde_json::Error),
 
-	/// The output did not contain any json
+	/// The output contained a top-level field that is not known to this crate,
+	/// see `MetadataCommand::strict`.
+	#[error("unknown field `{field}` in the output of `wesl metadata`")]
+	#[non_exhaustive]
+	UnknownField {
+		/// The name of the unknown field.
+		field: String,
+	},
+
+	/// The metadata has no `resolve` graph, which is needed for the dependents of a package,
+	/// e.g. because of `MetadataCommand::set_no_dependencies`.
+	#[error("the metadata has no `resolve` graph")]
+	MissingResolve,
+
+	/// The output was not empty but did not contain any json.
 	#[error("could not find any json in the output of `wesl metadata`")]
 	NoJson,
+
+	/// `wesl metadata` succeeded without printing anything but whitespace.
+	#[error("`wesl metadata` did not print anything")]
+	EmptyOutput,
+}
+
+/// Displays an optional exit code.
+struct ExitCode(Option<i32>);
+
+impl fmt::Display for ExitCode {
+	fn fmt(
+		&self,
+		formatter: &mut fmt::Formatter<'_>,
+	) -> fmt::Result {
+		match self.0 {
+			Some(code) => write!(formatter, "exit code {code}"),
+			None => formatter.write_str("no exit code"),
+		}
+	}
+}
+
+impl Error {
+	const fn json(&self) -> Option<&::serde_json::Error> {
+		if let Self::Json(error) = self {
+			Some(error)
+		} else {
+			None
+		}
+	}
+
+	/// Returns true for [`Error::Io`].
+	#[must_use]
+	pub const fn is_io(&self) -> bool {
+		matches!(self, Self::Io(_))
+	}
+
+	/// Returns true for [`Error::Json`].
+	#[must_use]
+	pub const fn is_json(&self) -> bool {
+		matches!(self, Self::Json(_))
+	}
+
+	/// Returns true for [`Error::Utf8`] and [`Error::ErrUtf8`].
+	#[must_use]
+	pub const fn is_utf8(&self) -> bool {
+		matches!(self, Self::Utf8(_) | Self::ErrUtf8(_))
+	}
+
+	/// Returns true for [`Error::WeslMetadata`], when `wesl metadata` itself failed.
+	#[must_use]
+	pub const fn is_wesl_error(&self) -> bool {
+		matches!(self, Self::WeslMetadata { .. })
+	}
+
+	/// Returns true for [`Error::NoJson`].
+	#[must_use]
+	pub const fn is_no_json(&self) -> bool {
+		matches!(self, Self::NoJson)
+	}
+
+	/// The line in the JSON output at which a [`Error::Json`] error occurred.
+	#[must_use]
+	pub fn line(&self) -> Option<usize> {
+		self.json().map(::serde_json::Error::line)
+	}
+
+	/// The column in the JSON output at which a [`Error::Json`] error occurred.
+	#[must_use]
+	pub fn column(&self) -> Option<usize> {
+		self.json().map(::serde_json::Error::column)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::error::Error as _;
+
+	use crate::MetadataCommand;
+
+	#[test]
+	fn json_source() {
+		let error = MetadataCommand::parse("{\n\"packages\": [}").unwrap_err();
+		assert!(error.source().is_some());
+		assert_eq!(error.line(), Some(2));
+		assert_eq!(error.column(), Some(14));
+	}
+
+	#[test]
+	fn utf8_source() {
+		let error = crate::Error::from(String::from_utf8(vec![0xff]).unwrap_err());
+		assert!(error.source().is_some());
+		assert_eq!(error.line(), None);
+	}
+
+	#[test]
+	fn classifiers() {
+		let json = MetadataCommand::parse("{").unwrap_err();
+		assert!(json.is_json());
+		assert!(!json.is_io() && !json.is_utf8() && !json.is_wesl_error() && !json.is_no_json());
+
+		let io = crate::Error::from(std::io::Error::other("broken pipe"));
+		assert!(io.is_io() && !io.is_json());
+		assert!(crate::Error::from(String::from_utf8(vec![0xff]).unwrap_err()).is_utf8());
+		assert!(crate::Error::NoJson.is_no_json());
+		let wesl = crate::Error::WeslMetadata {
+			exit_code: Some(1),
+			stderr: String::new(),
+			stdout: String::new(),
+		};
+		assert!(wesl.is_wesl_error() && !wesl.is_io());
+	}
 }
@@ -0,0 +1,202 @@
+//! This module contains the resolution of the `wesl` executable.
+
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{Error, MetadataCommand, Result};
+
+/// The extensions tried on Windows for an executable given without one.
+const WINDOWS_EXTENSIONS: [&str; 3] = ["exe", "cmd", "bat"];
+
+/// The paths that may be the executable `program`, in the order they are tried.
+///
+/// A `program` containing a separator is a path, otherwise it is searched in the directories
+/// of `search_path`. On `windows`, `\` is a separator too, and a name without one of the
+/// executable extensions is tried with each of them appended first. The name itself is then
+/// only tried if it has another extension, as an extensionless file like the shell script
+/// installed next to an npm `.cmd` shim cannot be run.
+pub(crate) fn candidates(
+	program: &Path,
+	search_path: Option<&OsStr>,
+	windows: bool,
+) -> Vec<PathBuf> {
+	let name = program.as_os_str().to_string_lossy();
+	let lowercase = name.to_ascii_lowercase();
+	let mut names = Vec::new();
+	if windows
+		&& !WINDOWS_EXTENSIONS
+			.iter()
+			.any(|extension| lowercase.ends_with(&format!(".{extension}")))
+	{
+		names.extend(WINDOWS_EXTENSIONS.iter().map(|extension| {
+			let mut name = program.as_os_str().to_owned();
+			name.push(".");
+			name.push(extension);
+			name
+		}));
+	}
+	if names.is_empty() || program.extension().is_some() {
+		names.push(program.as_os_str().to_owned());
+	}
+
+	let separators: &[char] = if windows { &['/', '\\'] } else { &['/'] };
+	if name.contains(separators) {
+		return names.into_iter().map(PathBuf::from).collect();
+	}
+	search_path
+		.map(env::split_paths)
+		.into_iter()
+		.flatten()
+		.flat_map(|directory| names.iter().map(move |name| directory.join(name)))
+		.collect()
+}
+
+/// Whether `path` is a file that can be run: on Unix, one with an executable permission bit
+/// set, like `which` checks.
+fn is_executable(path: &Path) -> bool {
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::PermissionsExt as _;
+
+		fs::metadata(path)
+			.is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+	}
+	#[cfg(not(unix))]
+	{
+		fs::metadata(path).is_ok_and(|metadata| metadata.is_file())
+	}
+}
+
+impl MetadataCommand {
+	/// The configured `wesl` executable: [`Self::wesl_path`], else `$WESL`, else `wesl`.
+	pub(crate) fn wesl_program(&self) -> PathBuf {
+		self.wesl_path
+			.clone()
+			.or_else(|| env::var_os("WESL").map(PathBuf::from))
+			.unwrap_or_else(|| PathBuf::from("wesl"))
+	}
+
+	/// The `PATH` that the `wesl` process gets.
+	fn search_path(&self) -> Option<OsString> {
+		match self.env.get(OsStr::new("PATH")) {
+			Some(value) => value.clone(),
+			None if self.env_clear => None,
+			None => env::var_os("PATH"),
+		}
+	}
+
+	/// Find the `wesl` executable to run among the [`candidates`] of [`Self::wesl_program`].
+	///
+	/// The first candidate that is an executable file is used, so a file without execute
+	/// permission on Unix does not shadow one later in `PATH`. Relative candidates are checked
+	/// in the current directory, if one is set. If there are no candidates because the search
+	/// path is empty, the program is returned as it is.
+	pub(crate) fn resolve_wesl(&self) -> Result<PathBuf> {
+		let program = self.wesl_program();
+		let candidates = candidates(&program, self.search_path().as_deref(), cfg!(windows));
+		if candidates.is_empty() {
+			return Ok(program);
+		}
+		candidates
+			.iter()
+			.find(|candidate| is_executable(&self.in_current_dir(candidate)))
+			.cloned()
+			.ok_or(Error::WeslNotFound {
+				attempted: program,
+				candidates,
+			})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn path_candidates() {
+		assert_eq!(
+			candidates(Path::new("./bin/wesl"), None, false),
+			[PathBuf::from("./bin/wesl")]
+		);
+		assert_eq!(
+			candidates(Path::new(r"C:\Program Files\wesl"), None, true),
+			[
+				PathBuf::from(r"C:\Program Files\wesl.exe"),
+				PathBuf::from(r"C:\Program Files\wesl.cmd"),
+				PathBuf::from(r"C:\Program Files\wesl.bat"),
+			]
+		);
+		assert_eq!(
+			candidates(Path::new("./bin/wesl.com"), None, true),
+			[
+				PathBuf::from("./bin/wesl.com.exe"),
+				PathBuf::from("./bin/wesl.com.cmd"),
+				PathBuf::from("./bin/wesl.com.bat"),
+				PathBuf::from("./bin/wesl.com"),
+			]
+		);
+		assert_eq!(
+			candidates(Path::new(r"C:\tools\wesl.EXE"), None, true),
+			[PathBuf::from(r"C:\tools\wesl.EXE")]
+		);
+	}
+
+	#[test]
+	fn search_path_candidates() {
+		let search_path = env::join_paths(["first", "second"]).unwrap();
+		assert_eq!(
+			candidates(Path::new("wesl"), Some(&search_path), false),
+			[
+				Path::new("first").join("wesl"),
+				Path::new("second").join("wesl")
+			]
+		);
+		assert_eq!(
+			candidates(Path::new("wesl"), Some(&search_path), true),
+			[
+				Path::new("first").join("wesl.exe"),
+				Path::new("first").join("wesl.cmd"),
+				Path::new("first").join("wesl.bat"),
+				Path::new("second").join("wesl.exe"),
+				Path::new("second").join("wesl.cmd"),
+				Path::new("second").join("wesl.bat"),
+			]
+		);
+		assert!(candidates(Path::new("wesl"), None, false).is_empty());
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn resolve_wesl() {
+		let temporary = crate::tests::TempDir::new("resolve-wesl");
+		let shim = temporary.shim("resolve-wesl", "");
+		let directory = temporary.path().as_std_path();
+		let mut command = MetadataCommand::new();
+		command.env("PATH", directory).wesl_path("resolve-wesl");
+		assert_eq!(command.resolve_wesl().unwrap(), shim);
+
+		command.wesl_path("resolve-wesl-missing");
+		assert!(matches!(
+			command.exec().unwrap_err(),
+			Error::WeslNotFound { attempted, candidates }
+				if attempted == Path::new("resolve-wesl-missing")
+					&& candidates == [directory.join("resolve-wesl-missing")]
+		));
+
+		command.env_remove("PATH");
+		assert_eq!(
+			command.resolve_wesl().unwrap(),
+			Path::new("resolve-wesl-missing")
+		);
+
+		// a file that is not executable does not shadow the executable later in `PATH`
+		temporary.write("plain/resolve-wesl", "#!/bin/sh\n");
+		let plain = directory.join("plain");
+		command
+			.env("PATH", env::join_paths([&plain, directory]).unwrap())
+			.wesl_path("resolve-wesl");
+		assert_eq!(command.resolve_wesl().unwrap(), shim);
+	}
+}
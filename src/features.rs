@@ -0,0 +1,425 @@
+//! This module contains [`resolve_features`] and the types it uses.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{Dependency, DependencyKind, Metadata, Node, Package, PackageId};
+
+/// The features selected on the root package(s) when resolving features.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct FeaturesSpec {
+	/// Features to enable, like `--features`.
+	pub features: Vec<String>,
+
+	/// Enable all features, like `--all-features`.
+	pub all_features: bool,
+
+	/// Do not enable the `default` feature, like `--no-default-features`.
+	pub no_default_features: bool,
+}
+
+/// A parsed entry of a feature's list in the `features` table.
+enum FeatureValue<'value> {
+	/// `feature`: another feature of the same package, or an optional dependency.
+	Feature(&'value str),
+	/// `dep:name`: an optional dependency.
+	Dependency(&'value str),
+	/// `name/feature` or `name?/feature`: a feature of a dependency.
+	DependencyFeature {
+		dependency: &'value str,
+		feature: &'value str,
+		/// `name?/feature` only enables the feature if the dependency is otherwise enabled.
+		weak: bool,
+	},
+}
+
+impl<'value> FeatureValue<'value> {
+	fn parse(value: &'value str) -> Self {
+		if let Some(dependency) = value.strip_prefix("dep:") {
+			Self::Dependency(dependency)
+		} else if let Some((dependency, feature)) = value.split_once('/') {
+			match dependency.strip_suffix('?') {
+				Some(dependency) => Self::DependencyFeature {
+					dependency,
+					feature,
+					weak: true,
+				},
+				None => Self::DependencyFeature {
+					dependency,
+					feature,
+					weak: false,
+				},
+			}
+		} else {
+			Self::Feature(value)
+		}
+	}
+}
+
+/// Compute the features enabled on each package, starting from `root_selection` on the root
+/// package, or on every workspace member if there is no root package.
+///
+/// This follows the usual unification rules:
+/// - the `default` feature is enabled unless disabled by the root selection or the dependency,
+/// - features enable the other features listed for them in the `features` table,
+/// - `dep:name` and a feature named like an optional dependency enable that dependency,
+/// - `name/feature` enables the dependency `name` and its feature `feature`,
+/// - `name?/feature` enables `feature` only if the dependency `name` is otherwise enabled,
+/// - features requested on the same package through different paths are unioned.
+///
+/// Dev-dependencies are only followed from the starting packages. Dependencies are matched to
+/// packages through `resolve` when present, or by name otherwise, in which case the first
+/// package with the name is used, even if several versions of it are present.
+/// Only packages reached through enabled dependencies appear in the returned map.
+#[must_use]
+pub fn resolve_features(
+	metadata: &Metadata,
+	root_selection: &FeaturesSpec,
+) -> BTreeMap<PackageId, BTreeSet<String>> {
+	Resolver::new(metadata).resolve(metadata, root_selection)
+}
+
+struct Resolver<'meta> {
+	packages: BTreeMap<&'meta PackageId, &'meta Package>,
+	nodes: Option<BTreeMap<&'meta PackageId, &'meta Node>>,
+	all_packages: &'meta [Package],
+	/// The packages features are resolved from, whose dev-dependencies are followed.
+	roots: BTreeSet<&'meta PackageId>,
+	enabled: BTreeMap<&'meta PackageId, BTreeSet<String>>,
+	activated: BTreeSet<(&'meta PackageId, &'meta str)>,
+}
+
+impl<'meta> Resolver<'meta> {
+	fn new(metadata: &'meta Metadata) -> Self {
+		Self {
+			packages: metadata.index_by_id(),
+			nodes: metadata
+				.resolve
+				.as_ref()
+				.map(|resolve| resolve.nodes.iter().map(|node| (&node.id, node)).collect()),
+			all_packages: &metadata.packages,
+			roots: BTreeSet::new(),
+			enabled: BTreeMap::new(),
+			activated: BTreeSet::new(),
+		}
+	}
+
+	fn resolve(
+		mut self,
+		metadata: &'meta Metadata,
+		root_selection: &FeaturesSpec,
+	) -> BTreeMap<PackageId, BTreeSet<String>> {
+		let roots = metadata
+			.root_package()
+			.map_or_else(|| metadata.workspace_member_packages(), |root| vec![root]);
+		for root in roots {
+			let mut features: Vec<&str> =
+				root_selection.features.iter().map(String::as_str).collect();
+			if !root_selection.no_default_features {
+				features.push("default");
+			}
+			if root_selection.all_features {
+				features.extend(root.features.keys().map(String::as_str));
+				features.extend(
+					root.dependencies
+						.iter()
+						.filter(|dependency| dependency.optional)
+						.map(Dependency::effective_name),
+				);
+			}
+			self.roots.insert(&root.id);
+			self.enabled.entry(&root.id).or_default();
+			for feature in features {
+				self.enable(&root.id, feature);
+			}
+		}
+
+		// every step only adds features and dependencies, so this reaches a fixed point
+		while self.step() {}
+
+		self.enabled
+			.into_iter()
+			.map(|(id, features)| (id.clone(), features))
+			.collect()
+	}
+
+	/// Enable `feature` on the package `id` if it has such a feature.
+	/// Returns true if the feature was not enabled before.
+	fn enable(
+		&mut self,
+		id: &'meta PackageId,
+		feature: &str,
+	) -> bool {
+		let Some(package) = self.packages.get(id) else {
+			return false;
+		};
+		if !package.has_feature(feature) {
+			return false;
+		}
+		self.enabled
+			.entry(id)
+			.or_default()
+			.insert(feature.to_owned())
+	}
+
+	/// The package a dependency of `package` resolves to.
+	///
+	/// Without `resolve`, this is the first package named like the dependency, regardless of
+	/// its version.
+	fn dependency_id(
+		&self,
+		package: &Package,
+		dependency: &Dependency,
+	) -> Option<&'meta PackageId> {
+		let Some(nodes) = &self.nodes else {
+			return self
+				.all_packages
+				.iter()
+				.find(|candidate| candidate.name == dependency.name)
+				.map(|candidate| &candidate.id);
+		};
+		let node = nodes.get(&package.id)?;
+		node.renamed_dependencies
+			.iter()
+			.find(|renamed| renamed.name == dependency.effective_name())
+			.map(|renamed| &renamed.pkg)
+			.or_else(|| {
+				node.dependencies.iter().find(|id| {
+					self.packages
+						.get(id)
+						.is_some_and(|candidate| candidate.name == dependency.name)
+				})
+			})
+	}
+
+	/// Propagate the enabled features of every enabled package once.
+	/// Returns true if anything changed.
+	fn step(&mut self) -> bool {
+		let mut changed = false;
+		let ids: Vec<&'meta PackageId> = self.enabled.keys().copied().collect();
+		for id in ids {
+			let Some(package) = self.packages.get(id).copied() else {
+				continue;
+			};
+			let features = self.enabled.get(id).cloned().unwrap_or_default();
+
+			let mut own_features = Vec::new();
+			let mut dependency_features = Vec::new();
+			for feature in &features {
+				for value in package.features.get(feature).into_iter().flatten() {
+					match FeatureValue::parse(value) {
+						FeatureValue::Feature(name) => own_features.push(name),
+						FeatureValue::Dependency(name) => {
+							changed |= self.activated.insert((id, name));
+						},
+						FeatureValue::DependencyFeature {
+							dependency,
+							feature,
+							weak,
+						} => {
+							if !weak {
+								changed |= self.activated.insert((id, dependency));
+							}
+							dependency_features.push((dependency, feature));
+						},
+					}
+				}
+				if let Some(dependency) = package.dependencies.iter().find(|dependency| {
+					dependency.optional && dependency.effective_name() == feature
+				}) {
+					changed |= self.activated.insert((id, dependency.effective_name()));
+				}
+			}
+			for feature in own_features {
+				changed |= self.enable(id, feature);
+			}
+
+			for dependency in &package.dependencies {
+				let name = dependency.effective_name();
+				if dependency.optional && !self.activated.contains(&(id, name)) {
+					continue;
+				}
+				if dependency.kind == DependencyKind::Dev && !self.roots.contains(id) {
+					continue;
+				}
+				let Some(target) = self.dependency_id(package, dependency) else {
+					continue;
+				};
+				if !self.enabled.contains_key(target) {
+					self.enabled.insert(target, BTreeSet::new());
+					changed = true;
+				}
+				if dependency.uses_default_features {
+					changed |= self.enable(target, "default");
+				}
+				for feature in &dependency.features {
+					changed |= self.enable(target, feature);
+				}
+				for (_, feature) in dependency_features
+					.iter()
+					.filter(|(dependency, _)| *dependency == name)
+				{
+					changed |= self.enable(target, feature);
+				}
+			}
+		}
+		changed
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tests::{metadata, package};
+
+	fn dependency(
+		name: &str,
+		features: &[&str],
+	) -> Dependency {
+		Dependency {
+			name: name.to_owned(),
+			rename: None,
+			path: None,
+			git: None,
+			kind: DependencyKind::Normal,
+			optional: false,
+			uses_default_features: true,
+			features: features
+				.iter()
+				.map(|feature| (*feature).to_owned())
+				.collect(),
+		}
+	}
+
+	fn with_features(
+		mut package: Package,
+		features: &[(&str, &[&str])],
+	) -> Package {
+		package.features = features
+			.iter()
+			.map(|(name, values)| {
+				(
+					(*name).to_owned(),
+					values.iter().map(|value| (*value).to_owned()).collect(),
+				)
+			})
+			.collect();
+		package
+	}
+
+	fn enabled<'result>(
+		result: &'result BTreeMap<PackageId, BTreeSet<String>>,
+		id: &str,
+	) -> Option<Vec<&'result str>> {
+		result
+			.get(&PackageId {
+				repr: id.to_owned(),
+			})
+			.map(|features| features.iter().map(String::as_str).collect())
+	}
+
+	fn root_metadata(packages: Vec<Package>) -> Metadata {
+		let mut metadata = metadata(packages);
+		metadata.root_package_directory = "/app".into();
+		metadata
+	}
+
+	#[test]
+	fn transitive_unioning() {
+		let mut app = package("app", "1.0.0");
+		app.dependencies = vec![dependency("lib", &["a"]), dependency("util", &[])];
+		let mut util = package("util", "1.0.0");
+		util.dependencies = vec![dependency("lib", &["b"])];
+		let lib = with_features(
+			package("lib", "1.0.0"),
+			&[
+				("default", &["c"]),
+				("a", &[]),
+				("b", &["d"]),
+				("c", &[]),
+				("d", &[]),
+				("unused", &[]),
+			],
+		);
+		let result = resolve_features(
+			&root_metadata(vec![app, util, lib]),
+			&FeaturesSpec::default(),
+		);
+		assert_eq!(
+			enabled(&result, "lib@1.0.0").unwrap(),
+			["a", "b", "c", "d", "default"]
+		);
+		assert_eq!(enabled(&result, "app@1.0.0").unwrap(), Vec::<&str>::new());
+		assert_eq!(enabled(&result, "util@1.0.0").unwrap(), Vec::<&str>::new());
+	}
+
+	#[test]
+	fn dev_dependencies_of_roots_only() {
+		let mut app = package("app", "1.0.0");
+		let mut tester = dependency("tester", &["fast"]);
+		tester.kind = DependencyKind::Dev;
+		app.dependencies = vec![dependency("lib", &[]), tester.clone()];
+		let mut lib = package("lib", "1.0.0");
+		tester.features = vec!["slow".to_owned()];
+		lib.dependencies = vec![tester, dependency("util", &[])];
+		let tester = with_features(package("tester", "1.0.0"), &[("fast", &[]), ("slow", &[])]);
+		let result = resolve_features(
+			&root_metadata(vec![app, lib, tester, package("util", "1.0.0")]),
+			&FeaturesSpec::default(),
+		);
+		assert_eq!(enabled(&result, "tester@1.0.0").unwrap(), ["fast"]);
+		assert!(enabled(&result, "util@1.0.0").is_some());
+	}
+
+	#[test]
+	fn no_default_features() {
+		let app = with_features(
+			package("app", "1.0.0"),
+			&[
+				("default", &["fancy"]),
+				("fancy", &["fancy"]),
+				("extra", &[]),
+			],
+		);
+		let metadata = root_metadata(vec![app]);
+
+		let result = resolve_features(&metadata, &FeaturesSpec::default());
+		assert_eq!(enabled(&result, "app@1.0.0").unwrap(), ["default", "fancy"]);
+
+		let selection = FeaturesSpec {
+			features: vec!["extra".to_owned()],
+			no_default_features: true,
+			..FeaturesSpec::default()
+		};
+		let result = resolve_features(&metadata, &selection);
+		assert_eq!(enabled(&result, "app@1.0.0").unwrap(), ["extra"]);
+	}
+
+	#[test]
+	fn weak_dependency_feature() {
+		let mut optional = dependency("opt", &[]);
+		optional.optional = true;
+		let mut app = with_features(
+			package("app", "1.0.0"),
+			&[("weak", &["opt?/fast"]), ("strong", &["dep:opt"])],
+		);
+		app.dependencies = vec![optional];
+		let opt = with_features(package("opt", "1.0.0"), &[("fast", &[])]);
+		let metadata = root_metadata(vec![app, opt]);
+
+		let selection = FeaturesSpec {
+			features: vec!["weak".to_owned()],
+			..FeaturesSpec::default()
+		};
+		let result = resolve_features(&metadata, &selection);
+		assert_eq!(enabled(&result, "app@1.0.0").unwrap(), ["weak"]);
+		assert_eq!(enabled(&result, "opt@1.0.0"), None);
+
+		let selection = FeaturesSpec {
+			features: vec!["weak".to_owned(), "strong".to_owned()],
+			..FeaturesSpec::default()
+		};
+		let result = resolve_features(&metadata, &selection);
+		assert_eq!(enabled(&result, "opt@1.0.0").unwrap(), ["fast"]);
+	}
+}
@@ -0,0 +1,418 @@
+//! This module contains [`Metadata::fingerprint`] and the hashes it uses.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::mem;
+
+use camino::Utf8PathBuf;
+use serde_json::Value;
+
+use crate::rebase::strip_prefix;
+use crate::{Metadata, PackageId};
+
+impl Metadata {
+	/// A stable 64-bit fingerprint of the dependency universe, e.g. for a build cache key.
+	///
+	/// The fingerprint is the 64-bit FNV-1a hash of the JSON form of a canonical copy of this
+	/// metadata, so it is the same on every machine. It is not stable across versions of this
+	/// crate: the canonical form changes whenever a field is added to [`Metadata`] or
+	/// [`crate::Package`], so do not keep fingerprints across upgrades. The canonical copy:
+	/// - includes `package_manager`, `version` and every field of every package,
+	///   including the free-form `metadata`, whose object keys are sorted,
+	/// - has its packages sorted by id, its workspace members sorted, and its `resolve` nodes
+	///   sorted by id with their dependencies sorted,
+	/// - has its paths made relative to `root_package_directory` like [`Self::relativize`],
+	///   as well as the paths in `path+file://` package ids, so moving the workspace does not
+	///   change the fingerprint, while paths outside of it, e.g. of registry packages, are
+	///   kept as they are,
+	/// - excludes `target_directory` and `root_package_directory`.
+	///
+	/// The order of the dependencies, features and targets of a package is kept, as it comes
+	/// from the manifest.
+	#[must_use]
+	pub fn fingerprint(&self) -> u64 {
+		fnv1a_64(&self.canonical_json())
+	}
+
+	/// A 32-byte fingerprint of the same canonical form as [`Self::fingerprint`], as 64
+	/// lowercase hexadecimal digits.
+	///
+	/// This is the SHA-256 hash of the canonical form.
+	#[must_use]
+	pub fn fingerprint_hex(&self) -> String {
+		hex(&sha256(&self.canonical_json()))
+	}
+
+	/// The JSON form of the canonical copy described by [`Self::fingerprint`].
+	fn canonical_json(&self) -> Vec<u8> {
+		let mut canonical = self.clone();
+		canonical.relativize();
+		let root = self.root_package_directory.as_str();
+		for package in &mut canonical.packages {
+			relativize_id(&mut package.id, root);
+			sort_keys(&mut package.metadata);
+		}
+		for member in &mut canonical.workspace_members {
+			relativize_id(member, root);
+		}
+		if let Some(resolve) = &mut canonical.resolve {
+			let ids = resolve.nodes.iter_mut().flat_map(|node| {
+				node.dependencies
+					.iter_mut()
+					.chain(
+						node.renamed_dependencies
+							.iter_mut()
+							.map(|dependency| &mut dependency.pkg),
+					)
+					.chain([&mut node.id])
+			});
+			for id in ids.chain(resolve.root.as_mut()) {
+				relativize_id(id, root);
+			}
+		}
+		canonical.target_directory = Utf8PathBuf::new();
+		canonical.root_package_directory = Utf8PathBuf::new();
+		canonical
+			.packages
+			.sort_by(|first, second| first.id.cmp(&second.id));
+		canonical.workspace_members.sort();
+		if let Some(resolve) = &mut canonical.resolve {
+			resolve
+				.nodes
+				.sort_by(|first, second| first.id.cmp(&second.id));
+			for node in &mut resolve.nodes {
+				node.dependencies.sort();
+				node.renamed_dependencies.sort_by(|first, second| {
+					(&first.pkg, &first.name).cmp(&(&second.pkg, &second.name))
+				});
+			}
+		}
+		serde_json::to_vec(&canonical).expect("metadata always serializes to json")
+	}
+}
+
+/// Make the path of a `path+file://<path>` id relative to `root`, if it is inside of it.
+fn relativize_id(
+	id: &mut PackageId,
+	root: &str,
+) {
+	let Some(rest) = id.repr.strip_prefix("path+file://") else {
+		return;
+	};
+	let end = rest.find(['#', '?']).unwrap_or(rest.len());
+	if let Some(relative) = strip_prefix(&rest[..end], root) {
+		id.repr = format!("path+file://{relative}{}", &rest[end..]);
+	}
+}
+
+/// Sort the keys of every object in `value`, which otherwise keep their insertion order
+/// with the `preserve_order` feature of `serde_json`.
+fn sort_keys(value: &mut Value) {
+	match value {
+		Value::Object(object) => {
+			let sorted: BTreeMap<_, _> = mem::take(object).into_iter().collect();
+			for (key, mut value) in sorted {
+				sort_keys(&mut value);
+				object.insert(key, value);
+			}
+		},
+		Value::Array(values) => values.iter_mut().for_each(sort_keys),
+		Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_) => {},
+	}
+}
+
+/// The 64-bit FNV-1a hash of `bytes`.
+fn fnv1a_64(bytes: &[u8]) -> u64 {
+	const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+	const PRIME: u64 = 0x0000_0100_0000_01b3;
+	bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+		(hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+	})
+}
+
+/// `bytes` as lowercase hexadecimal digits.
+fn hex(bytes: &[u8]) -> String {
+	bytes
+		.iter()
+		.fold(String::with_capacity(bytes.len() * 2), |mut hex, byte| {
+			write!(hex, "{byte:02x}").expect("writing to a string cannot fail");
+			hex
+		})
+}
+
+/// The round constants of SHA-256.
+const SHA256_ROUND_CONSTANTS: [u32; 64] = [
+	0x428a_2f98,
+	0x7137_4491,
+	0xb5c0_fbcf,
+	0xe9b5_dba5,
+	0x3956_c25b,
+	0x59f1_11f1,
+	0x923f_82a4,
+	0xab1c_5ed5,
+	0xd807_aa98,
+	0x1283_5b01,
+	0x2431_85be,
+	0x550c_7dc3,
+	0x72be_5d74,
+	0x80de_b1fe,
+	0x9bdc_06a7,
+	0xc19b_f174,
+	0xe49b_69c1,
+	0xefbe_4786,
+	0x0fc1_9dc6,
+	0x240c_a1cc,
+	0x2de9_2c6f,
+	0x4a74_84aa,
+	0x5cb0_a9dc,
+	0x76f9_88da,
+	0x983e_5152,
+	0xa831_c66d,
+	0xb003_27c8,
+	0xbf59_7fc7,
+	0xc6e0_0bf3,
+	0xd5a7_9147,
+	0x06ca_6351,
+	0x1429_2967,
+	0x27b7_0a85,
+	0x2e1b_2138,
+	0x4d2c_6dfc,
+	0x5338_0d13,
+	0x650a_7354,
+	0x766a_0abb,
+	0x81c2_c92e,
+	0x9272_2c85,
+	0xa2bf_e8a1,
+	0xa81a_664b,
+	0xc24b_8b70,
+	0xc76c_51a3,
+	0xd192_e819,
+	0xd699_0624,
+	0xf40e_3585,
+	0x106a_a070,
+	0x19a4_c116,
+	0x1e37_6c08,
+	0x2748_774c,
+	0x34b0_bcb5,
+	0x391c_0cb3,
+	0x4ed8_aa4a,
+	0x5b9c_ca4f,
+	0x682e_6ff3,
+	0x748f_82ee,
+	0x78a5_636f,
+	0x84c8_7814,
+	0x8cc7_0208,
+	0x90be_fffa,
+	0xa450_6ceb,
+	0xbef9_a3f7,
+	0xc671_78f2,
+];
+
+/// The SHA-256 hash of `bytes`, as specified in FIPS 180-4.
+#[expect(
+	clippy::big_endian_bytes,
+	reason = "SHA-256 is specified on big-endian words"
+)]
+fn sha256(bytes: &[u8]) -> [u8; 32] {
+	let mut state: [u32; 8] = [
+		0x6a09_e667,
+		0xbb67_ae85,
+		0x3c6e_f372,
+		0xa54f_f53a,
+		0x510e_527f,
+		0x9b05_688c,
+		0x1f83_d9ab,
+		0x5be0_cd19,
+	];
+
+	// the message, a one bit, zeros up to a multiple of 64 bytes, and the length in bits
+	let bit_length = u64::try_from(bytes.len())
+		.expect("the length fits in 64 bits")
+		.wrapping_mul(8);
+	let mut message = bytes.to_vec();
+	message.push(0x80);
+	message.resize((bytes.len() + 9).next_multiple_of(64) - 8, 0);
+	message.extend(bit_length.to_be_bytes());
+
+	for block in message.chunks_exact(64) {
+		let mut schedule = [0_u32; 64];
+		for (word, word_bytes) in schedule.iter_mut().zip(block.chunks_exact(4)) {
+			*word = u32::from_be_bytes(word_bytes.try_into().expect("chunks have 4 bytes"));
+		}
+		sha256_compress(&mut state, &mut schedule);
+	}
+
+	let mut digest = [0_u8; 32];
+	for (chunk, value) in digest.chunks_exact_mut(4).zip(state) {
+		chunk.copy_from_slice(&value.to_be_bytes());
+	}
+	digest
+}
+
+/// Update the SHA-256 `state` with a block, whose first 16 words are in `schedule`.
+fn sha256_compress(
+	state: &mut [u32; 8],
+	schedule: &mut [u32; 64],
+) {
+	for index in 16..64 {
+		let before = schedule[index - 15];
+		let sigma0 = before.rotate_right(7) ^ before.rotate_right(18) ^ (before >> 3);
+		let previous = schedule[index - 2];
+		let sigma1 = previous.rotate_right(17) ^ previous.rotate_right(19) ^ (previous >> 10);
+		schedule[index] = schedule[index - 16]
+			.wrapping_add(sigma0)
+			.wrapping_add(schedule[index - 7])
+			.wrapping_add(sigma1);
+	}
+
+	let mut working = *state;
+	for (constant, word) in SHA256_ROUND_CONSTANTS.iter().zip(schedule) {
+		let [first, second, third, fourth, fifth, sixth, seventh, eighth] = working;
+		let sum1 = fifth.rotate_right(6) ^ fifth.rotate_right(11) ^ fifth.rotate_right(25);
+		let choice = (fifth & sixth) ^ (!fifth & seventh);
+		let mixed = eighth
+			.wrapping_add(sum1)
+			.wrapping_add(choice)
+			.wrapping_add(*constant)
+			.wrapping_add(*word);
+		let sum0 = first.rotate_right(2) ^ first.rotate_right(13) ^ first.rotate_right(22);
+		let majority = (first & second) ^ (first & third) ^ (second & third);
+		working = [
+			mixed.wrapping_add(sum0.wrapping_add(majority)),
+			first,
+			second,
+			third,
+			fourth.wrapping_add(mixed),
+			fifth,
+			sixth,
+			seventh,
+		];
+	}
+	for (value, added) in state.iter_mut().zip(working) {
+		*value = value.wrapping_add(added);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tests::{metadata, node, package};
+	use crate::{PackageId, Resolve};
+
+	#[test]
+	fn fnv1a() {
+		assert_eq!(fnv1a_64(b""), 0xcbf2_9ce4_8422_2325);
+		assert_eq!(fnv1a_64(b"a"), 0xaf63_dc4c_8601_ec8c);
+	}
+
+	#[test]
+	fn sha256() {
+		let digest = |bytes: &[u8]| hex(&super::sha256(bytes));
+		assert_eq!(
+			digest(b""),
+			"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+		);
+		assert_eq!(
+			digest(b"abc"),
+			"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+		);
+		// two blocks, as the length no longer fits after the padding of the first
+		assert_eq!(
+			digest(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+			"248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+		);
+	}
+
+	fn fixture(
+		root: &str,
+		lib_version: &str,
+	) -> Metadata {
+		let mut app = package("app", "1.0.0");
+		app.manifest_path = format!("{root}/wesl.toml").into();
+		let lib = package("lib", lib_version);
+		let lib_id = lib.id.to_string();
+		let mut metadata = metadata(vec![app, lib]);
+		metadata.root_package_directory = root.into();
+		metadata.target_directory = format!("{root}/target").into();
+		metadata.resolve = Some(Resolve {
+			nodes: vec![node("app@1.0.0", &[&lib_id]), node(&lib_id, &[])],
+			root: Some(PackageId::from("app@1.0.0")),
+		});
+		metadata
+	}
+
+	#[test]
+	fn fingerprint() {
+		let original = fixture("/home/ci/app", "1.0.0");
+		let fingerprint = original.fingerprint();
+		assert_eq!(original.fingerprint_hex().len(), 64);
+
+		let mut reordered = original.clone();
+		reordered.packages.reverse();
+		reordered.resolve.as_mut().unwrap().nodes.reverse();
+		assert_eq!(reordered.fingerprint(), fingerprint);
+		assert_eq!(reordered.fingerprint_hex(), original.fingerprint_hex());
+
+		let mut moved = fixture("/home/dev/projects/app", "1.0.0");
+		moved.target_directory = "/tmp/target".into();
+		assert_eq!(moved.fingerprint(), fingerprint);
+
+		let mut annotated = original.clone();
+		annotated.packages[0].metadata =
+			serde_json::json!({"tool": {"b": 1, "a": [{"d": 2, "c": 3}]}});
+		let mut reannotated = original.clone();
+		reannotated.packages[0].metadata =
+			serde_json::json!({"tool": {"a": [{"c": 3, "d": 2}], "b": 1}});
+		assert_eq!(annotated.fingerprint(), reannotated.fingerprint());
+
+		let bumped = fixture("/home/ci/app", "1.0.1");
+		assert_ne!(bumped.fingerprint(), fingerprint);
+		assert_ne!(bumped.fingerprint_hex(), original.fingerprint_hex());
+	}
+
+	fn path_fixture(root: &str) -> Metadata {
+		let app_id = format!("path+file://{root}#1.0.0");
+		let lib_id = format!("path+file://{root}/lib#lib@1.0.0");
+		let mut app = package("app", "1.0.0");
+		app.id = PackageId::from(app_id.as_str());
+		app.manifest_path = format!("{root}/wesl.toml").into();
+		let mut lib = package("lib", "1.0.0");
+		lib.id = PackageId::from(lib_id.as_str());
+		lib.manifest_path = format!("{root}/lib/wesl.toml").into();
+		let util = package("util", "1.0.0");
+		let mut metadata = metadata(vec![app, lib, util]);
+		metadata.workspace_members = vec![
+			PackageId::from(app_id.as_str()),
+			PackageId::from(lib_id.as_str()),
+		];
+		metadata.root_package_directory = root.into();
+		metadata.resolve = Some(Resolve {
+			nodes: vec![
+				node(&app_id, &[&lib_id, "util@1.0.0"]),
+				node(&lib_id, &[]),
+				node("util@1.0.0", &[]),
+			],
+			root: Some(PackageId::from(app_id.as_str())),
+		});
+		metadata
+	}
+
+	#[test]
+	fn fingerprint_path_ids() {
+		let original = path_fixture("/home/ci/app");
+		assert_eq!(
+			path_fixture("/home/dev/app").fingerprint(),
+			original.fingerprint()
+		);
+		assert_eq!(
+			path_fixture("/home/dev/app").fingerprint_hex(),
+			original.fingerprint_hex()
+		);
+
+		let mut outside = original;
+		outside.packages[2].id = PackageId::from("path+file:///home/ci/util#1.0.0");
+		let mut moved_outside = path_fixture("/home/dev/app");
+		moved_outside.packages[2].id = PackageId::from("path+file:///home/dev/util#1.0.0");
+		assert_ne!(moved_outside.fingerprint(), outside.fingerprint());
+	}
+}
@@ -0,0 +1,593 @@
+//! This module contains renderers for the dependency graph.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::{Metadata, Node, Package, PackageId, Resolve};
+
+/// Options for rendering the dependency graph in the Graphviz DOT language.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DotOptions {
+	/// Shape of the nodes, e.g. `box`. Graphviz's default shape is used if `None`.
+	pub node_shape: Option<String>,
+
+	/// Label edges of renamed dependencies with the name they are imported under.
+	pub rename_labels: bool,
+
+	/// Group packages into one cluster per [`Source`](crate::Source).
+	pub cluster_by_source: bool,
+
+	/// Color the root package. Only used by [`Metadata::to_dot`].
+	pub highlight_root: bool,
+
+	/// Color local packages and packages from a registry differently.
+	/// Only used by [`Metadata::to_dot`].
+	pub color_by_source: bool,
+
+	/// Only show this package and its transitive dependencies. Only used by [`Metadata::to_dot`].
+	pub focus: Option<PackageId>,
+
+	/// Show all versions of a package as a single node. Only used by [`Metadata::to_dot`].
+	pub collapse_versions: bool,
+}
+
+impl Resolve {
+	/// Render this graph in the Graphviz DOT language.
+	///
+	/// Nodes are labeled `name@version` using the matching entries of `packages`.
+	#[must_use]
+	pub fn to_dot(
+		&self,
+		packages: &[Package],
+	) -> String {
+		self.to_dot_with_options(packages, &DotOptions::default())
+	}
+
+	/// Render this graph in the Graphviz DOT language, configured by `options`.
+	#[must_use]
+	pub fn to_dot_with_options(
+		&self,
+		packages: &[Package],
+		options: &DotOptions,
+	) -> String {
+		let packages: BTreeMap<&PackageId, &Package> = packages
+			.iter()
+			.map(|package| (&package.id, package))
+			.collect();
+		let mut dot = Dot::new(options);
+		for node in &self.nodes {
+			let package = packages.get(&node.id);
+			dot.node(
+				node.id.repr.clone(),
+				label(&packages, &node.id),
+				package.copied(),
+				Vec::new(),
+			);
+		}
+		for node in &self.nodes {
+			for (dependency, rename) in edges(node) {
+				dot.edge(node.id.repr.clone(), dependency.repr.clone(), rename);
+			}
+		}
+		dot.to_string()
+	}
+}
+
+impl Metadata {
+	/// Render the dependency graph in the Graphviz DOT language, configured by `options`.
+	///
+	/// Nodes are labeled with the name and version of their package. Without a resolve graph,
+	/// only the packages are shown.
+	#[must_use]
+	pub fn to_dot(
+		&self,
+		options: &DotOptions,
+	) -> String {
+		let resolve = self.resolve.as_ref().map(|resolve| match &options.focus {
+			Some(focus) => Cow::Owned(resolve.subgraph(std::slice::from_ref(focus))),
+			None => Cow::Borrowed(resolve),
+		});
+		let included: Option<BTreeSet<&PackageId>> = match (&resolve, &options.focus) {
+			(Some(resolve), Some(_)) => Some(resolve.nodes.iter().map(|node| &node.id).collect()),
+			(None, Some(focus)) => Some(BTreeSet::from([focus])),
+			(_, None) => None,
+		};
+		let root = self.root_package().map(|package| &package.id);
+		let key = |package: &Package| {
+			if options.collapse_versions {
+				package.name.clone()
+			} else {
+				package.id.repr.clone()
+			}
+		};
+
+		let mut dot = Dot::new(options);
+		for package in &self.packages {
+			if included
+				.as_ref()
+				.is_some_and(|included| !included.contains(&package.id))
+			{
+				continue;
+			}
+			let mut attributes = Vec::new();
+			if options.highlight_root && root == Some(&package.id) {
+				attributes.push(("color", "red".to_owned()));
+			}
+			if options.color_by_source {
+				let color = if package.source.is_some() {
+					"lightgrey"
+				} else {
+					"lightblue"
+				};
+				attributes.push(("style", "filled".to_owned()));
+				attributes.push(("fillcolor", color.to_owned()));
+			}
+			if let Some(description) = &package.description {
+				attributes.push(("tooltip", description.clone()));
+			}
+			let label = if options.collapse_versions {
+				package.name.clone()
+			} else {
+				format!("{}\n{}", package.name, package.version)
+			};
+			dot.node(key(package), label, Some(package), attributes);
+		}
+
+		let packages = self.index_by_id();
+		for node in resolve.iter().flat_map(|resolve| &resolve.nodes) {
+			let Some(package) = packages.get(&node.id) else {
+				continue;
+			};
+			for (dependency, rename) in edges(node) {
+				if let Some(dependency) = packages.get(dependency) {
+					dot.edge(key(package), key(dependency), rename);
+				}
+			}
+		}
+		dot.to_string()
+	}
+}
+
+impl Resolve {
+	/// Render this graph as a Mermaid `graph TD` flowchart, e.g. for a GitHub README.
+	///
+	/// Node ids are the package ids with every character other than ASCII letters and digits
+	/// replaced by `_`, and nodes are labeled `name@version` using the matching entries of
+	/// `packages`. Edges of renamed dependencies are labeled with the name they are imported under.
+	#[must_use]
+	pub fn to_mermaid(
+		&self,
+		packages: &[Package],
+	) -> String {
+		let packages: BTreeMap<&PackageId, &Package> = packages
+			.iter()
+			.map(|package| (&package.id, package))
+			.collect();
+		let mut ids: BTreeMap<&PackageId, String> = BTreeMap::new();
+		for node in &self.nodes {
+			let mut id = mermaid_id(&node.id.repr);
+			while ids.values().any(|other| *other == id) {
+				id.push('_');
+			}
+			ids.insert(&node.id, id);
+		}
+		Mermaid {
+			resolve: self,
+			packages,
+			ids,
+		}
+		.to_string()
+	}
+}
+
+/// Iterate over the edges of a node, paired with the rename of the edge if there is one.
+///
+/// Edges in `renamed_dependencies` take precedence over the same edges in `dependencies`.
+pub(crate) fn edges(node: &Node) -> impl Iterator<Item = (&PackageId, Option<&str>)> {
+	let renamed = node
+		.renamed_dependencies
+		.iter()
+		.map(|dependency| (&dependency.pkg, Some(dependency.name.as_str())));
+	let plain = node
+		.dependencies
+		.iter()
+		.filter(|id| {
+			!node
+				.renamed_dependencies
+				.iter()
+				.any(|dependency| dependency.pkg == **id)
+		})
+		.map(|id| (id, None));
+	renamed.chain(plain)
+}
+
+/// Label of a package as `name@version`, or its id if the package is unknown.
+pub(crate) fn label(
+	packages: &BTreeMap<&PackageId, &Package>,
+	id: &PackageId,
+) -> String {
+	packages.get(id).map_or_else(
+		|| id.repr.clone(),
+		|package| format!("{}@{}", package.name, package.version),
+	)
+}
+
+/// Quote a string as a DOT identifier.
+struct Quoted<'text>(&'text str);
+
+impl fmt::Display for Quoted<'_> {
+	fn fmt(
+		&self,
+		formatter: &mut fmt::Formatter<'_>,
+	) -> fmt::Result {
+		formatter.write_str("\"")?;
+		for character in self.0.chars() {
+			match character {
+				'"' => formatter.write_str("\\\"")?,
+				'\\' => formatter.write_str("\\\\")?,
+				'\n' => formatter.write_str("\\n")?,
+				_ => fmt::Write::write_char(formatter, character)?,
+			}
+		}
+		formatter.write_str("\"")
+	}
+}
+
+/// Sanitize a package id into a Mermaid node id.
+fn mermaid_id(repr: &str) -> String {
+	let id: String = repr
+		.chars()
+		.map(|character| {
+			if character.is_ascii_alphanumeric() {
+				character
+			} else {
+				'_'
+			}
+		})
+		.collect();
+	// `end` is a keyword in Mermaid flowcharts
+	if id.eq_ignore_ascii_case("end") {
+		format!("{id}_")
+	} else {
+		id
+	}
+}
+
+/// Quote a string as a Mermaid label.
+struct MermaidLabel<'text>(&'text str);
+
+impl fmt::Display for MermaidLabel<'_> {
+	fn fmt(
+		&self,
+		formatter: &mut fmt::Formatter<'_>,
+	) -> fmt::Result {
+		formatter.write_str("\"")?;
+		for character in self.0.chars() {
+			match character {
+				'"' => formatter.write_str("#quot;")?,
+				'\n' => formatter.write_str(" ")?,
+				_ => fmt::Write::write_char(formatter, character)?,
+			}
+		}
+		formatter.write_str("\"")
+	}
+}
+
+struct Mermaid<'resolve> {
+	resolve: &'resolve Resolve,
+	packages: BTreeMap<&'resolve PackageId, &'resolve Package>,
+	ids: BTreeMap<&'resolve PackageId, String>,
+}
+
+impl Mermaid<'_> {
+	fn id<'id>(
+		&'id self,
+		id: &'id PackageId,
+	) -> &'id str {
+		self.ids.get(id).map_or(&id.repr, String::as_str)
+	}
+}
+
+impl fmt::Display for Mermaid<'_> {
+	fn fmt(
+		&self,
+		formatter: &mut fmt::Formatter<'_>,
+	) -> fmt::Result {
+		writeln!(formatter, "graph TD")?;
+		for node in &self.resolve.nodes {
+			writeln!(
+				formatter,
+				"    {}[{}]",
+				self.id(&node.id),
+				MermaidLabel(&label(&self.packages, &node.id))
+			)?;
+		}
+		for node in &self.resolve.nodes {
+			for (dependency, rename) in edges(node) {
+				let Some(dependency_id) = self.ids.get(dependency) else {
+					continue;
+				};
+				write!(formatter, "    {} -->", self.id(&node.id))?;
+				if let Some(rename) = rename {
+					write!(formatter, "|{}|", MermaidLabel(rename))?;
+				}
+				writeln!(formatter, " {dependency_id}")?;
+			}
+		}
+		Ok(())
+	}
+}
+
+/// A DOT node with the package it was created for.
+struct DotNode<'meta> {
+	key: String,
+	label: String,
+	package: Option<&'meta Package>,
+	attributes: Vec<(&'static str, String)>,
+}
+
+/// A DOT edge: the keys of its nodes and its label.
+type DotEdge<'meta> = (String, String, Option<&'meta str>);
+
+/// A DOT graph, deduplicating nodes and edges while keeping their order.
+struct Dot<'meta> {
+	options: &'meta DotOptions,
+	nodes: Vec<DotNode<'meta>>,
+	node_keys: BTreeSet<String>,
+	edges: Vec<DotEdge<'meta>>,
+	edge_set: BTreeSet<DotEdge<'meta>>,
+}
+
+impl<'meta> Dot<'meta> {
+	const fn new(options: &'meta DotOptions) -> Self {
+		Self {
+			options,
+			nodes: Vec::new(),
+			node_keys: BTreeSet::new(),
+			edges: Vec::new(),
+			edge_set: BTreeSet::new(),
+		}
+	}
+
+	fn node(
+		&mut self,
+		key: String,
+		label: String,
+		package: Option<&'meta Package>,
+		attributes: Vec<(&'static str, String)>,
+	) {
+		if self.node_keys.insert(key.clone()) {
+			self.nodes.push(DotNode {
+				key,
+				label,
+				package,
+				attributes,
+			});
+		}
+	}
+
+	fn edge(
+		&mut self,
+		from: String,
+		to: String,
+		rename: Option<&'meta str>,
+	) {
+		let rename = rename.filter(|_| self.options.rename_labels);
+		let edge = (from, to, rename);
+		if self.edge_set.insert(edge.clone()) {
+			self.edges.push(edge);
+		}
+	}
+
+	fn write_node(
+		formatter: &mut fmt::Formatter<'_>,
+		indent: &str,
+		node: &DotNode<'_>,
+	) -> fmt::Result {
+		write!(
+			formatter,
+			"{indent}{} [label={}",
+			Quoted(&node.key),
+			Quoted(&node.label)
+		)?;
+		for (name, value) in &node.attributes {
+			write!(formatter, ", {name}={}", Quoted(value))?;
+		}
+		writeln!(formatter, "];")
+	}
+}
+
+impl fmt::Display for Dot<'_> {
+	fn fmt(
+		&self,
+		formatter: &mut fmt::Formatter<'_>,
+	) -> fmt::Result {
+		writeln!(formatter, "digraph {{")?;
+		if let Some(shape) = &self.options.node_shape {
+			writeln!(formatter, "\tnode [shape={}];", Quoted(shape))?;
+		}
+
+		if self.options.cluster_by_source {
+			let mut clusters: BTreeMap<String, Vec<&DotNode<'_>>> = BTreeMap::new();
+			for node in &self.nodes {
+				let source = node
+					.package
+					.and_then(|package| package.source.as_ref())
+					.map_or_else(|| "local".to_owned(), ToString::to_string);
+				clusters.entry(source).or_default().push(node);
+			}
+			for (index, (source, nodes)) in clusters.iter().enumerate() {
+				writeln!(formatter, "\tsubgraph cluster_{index} {{")?;
+				writeln!(formatter, "\t\tlabel={};", Quoted(source))?;
+				for node in nodes {
+					Self::write_node(formatter, "\t\t", node)?;
+				}
+				writeln!(formatter, "\t}}")?;
+			}
+		} else {
+			for node in &self.nodes {
+				Self::write_node(formatter, "\t", node)?;
+			}
+		}
+
+		for (from, to, rename) in &self.edges {
+			write!(formatter, "\t{} -> {}", Quoted(from), Quoted(to))?;
+			match rename {
+				Some(rename) => writeln!(formatter, " [label={}];", Quoted(rename))?,
+				None => writeln!(formatter, ";")?,
+			}
+		}
+		writeln!(formatter, "}}")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tests::{metadata, node, package};
+	use crate::{NodeDependency, Source};
+
+	fn resolve() -> Resolve {
+		let mut app = node("app@1.0.0", &["util@1.0.0"]);
+		app.renamed_dependencies.push(NodeDependency {
+			name: "helpers".to_owned(),
+			pkg: PackageId {
+				repr: "util@1.0.0".to_owned(),
+			},
+		});
+		Resolve {
+			nodes: vec![app, node("util@1.0.0", &[])],
+			root: None,
+		}
+	}
+
+	#[test]
+	fn to_dot() {
+		let packages = [package("app", "1.0.0"), package("util", "1.0.0")];
+		assert_eq!(
+			resolve().to_dot(&packages),
+			"digraph {\n\t\"app@1.0.0\" [label=\"app@1.0.0\"];\n\t\"util@1.0.0\" [label=\"util@1.0.0\"];\n\t\"app@1.0.0\" -> \"util@1.0.0\";\n}\n"
+		);
+	}
+
+	#[test]
+	fn to_dot_with_options() {
+		let mut util = package("util", "1.0.0");
+		util.source = Some(Source {
+			representation: "registry+https://registry.npmjs.org/".to_owned(),
+		});
+		let packages = [package("app", "1.0.0"), util];
+		let options = DotOptions {
+			node_shape: Some("box".to_owned()),
+			rename_labels: true,
+			cluster_by_source: true,
+			..DotOptions::default()
+		};
+		assert_eq!(
+			resolve().to_dot_with_options(&packages, &options),
+			"digraph {\n\tnode [shape=\"box\"];\n\tsubgraph cluster_0 {\n\t\tlabel=\"local\";\n\t\t\"app@1.0.0\" [label=\"app@1.0.0\"];\n\t}\n\tsubgraph cluster_1 {\n\t\tlabel=\"registry+https://registry.npmjs.org/\";\n\t\t\"util@1.0.0\" [label=\"util@1.0.0\"];\n\t}\n\t\"app@1.0.0\" -> \"util@1.0.0\" [label=\"helpers\"];\n}\n"
+		);
+	}
+
+	#[test]
+	fn to_mermaid() {
+		let mut resolve = resolve();
+		resolve.nodes.push(node("end", &["app@1.0.0", "missing"]));
+		let packages = [package("app", "1.0.0"), package("util", "1.0.0")];
+		assert_eq!(
+			resolve.to_mermaid(&packages),
+			"graph TD\n    app_1_0_0[\"app@1.0.0\"]\n    util_1_0_0[\"util@1.0.0\"]\n    end_[\"end\"]\n    app_1_0_0 -->|\"helpers\"| util_1_0_0\n    end_ --> app_1_0_0\n"
+		);
+	}
+
+	#[test]
+	fn mermaid_id() {
+		assert_eq!(
+			super::mermaid_id("registry+https://example.com#a@1.0.0"),
+			"registry_https___example_com_a_1_0_0"
+		);
+		assert_eq!(super::mermaid_id("End"), "End_");
+	}
+
+	fn metadata_fixture() -> Metadata {
+		let mut app = package("app", "1.0.0");
+		app.description = Some("The \"main\" app".to_owned());
+		let mut noise_1 = package("noise", "1.0.0");
+		noise_1.source = Some(Source {
+			representation: "registry+https://registry.npmjs.org/".to_owned(),
+		});
+		let mut noise_2 = package("noise", "2.0.0");
+		noise_2.source = noise_1.source.clone();
+		let mut metadata = metadata(vec![
+			app,
+			package("lib", "1.0.0"),
+			noise_1,
+			noise_2,
+			package("tool", "1.0.0"),
+		]);
+		metadata.resolve = Some(Resolve {
+			nodes: vec![
+				node("app@1.0.0", &["lib@1.0.0", "noise@1.0.0"]),
+				node("lib@1.0.0", &["noise@2.0.0"]),
+				node("noise@1.0.0", &[]),
+				node("noise@2.0.0", &[]),
+				node("tool@1.0.0", &["noise@1.0.0", "noise@2.0.0"]),
+			],
+			root: Some(PackageId {
+				repr: "app@1.0.0".to_owned(),
+			}),
+		});
+		metadata
+	}
+
+	fn count(
+		dot: &str,
+		pattern: &str,
+	) -> usize {
+		dot.lines().filter(|line| line.contains(pattern)).count()
+	}
+
+	#[test]
+	fn metadata_to_dot() {
+		let metadata = metadata_fixture();
+		let dot = metadata.to_dot(&DotOptions::default());
+		assert_eq!(count(&dot, "[label="), 5);
+		assert_eq!(count(&dot, " -> "), 5);
+		assert!(dot.contains(
+			"\t\"app@1.0.0\" [label=\"app\\n1.0.0\", tooltip=\"The \\\"main\\\" app\"];\n"
+		));
+
+		let options = DotOptions {
+			highlight_root: true,
+			color_by_source: true,
+			collapse_versions: true,
+			..DotOptions::default()
+		};
+		let dot = metadata.to_dot(&options);
+		assert_eq!(count(&dot, "[label="), 4);
+		assert_eq!(count(&dot, " -> "), 4);
+		assert_eq!(count(&dot, "color=\"red\""), 1);
+		assert_eq!(count(&dot, "fillcolor=\"lightgrey\""), 1);
+		assert_eq!(count(&dot, "fillcolor=\"lightblue\""), 3);
+
+		let options = DotOptions {
+			focus: Some(PackageId {
+				repr: "lib@1.0.0".to_owned(),
+			}),
+			..DotOptions::default()
+		};
+		let dot = metadata.to_dot(&options);
+		assert_eq!(count(&dot, "[label="), 2);
+		assert_eq!(count(&dot, " -> "), 1);
+	}
+
+	#[test]
+	fn quoted() {
+		assert_eq!(
+			Quoted("a \"b\"\\c\n").to_string(),
+			"\"a \\\"b\\\"\\\\c\\n\""
+		);
+	}
+}
@@ -44,30 +44,64 @@
 //!     .unwrap();
 //! ```
 
-use camino::Utf8PathBuf;
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
 #[cfg(feature = "builder")]
 use derive_builder::Builder;
-use std::collections::BTreeMap;
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::env;
-use std::ffi::OsString;
+use std::ffi::{OsStr, OsString};
 use std::fmt;
 use std::hash::Hash;
-use std::path::PathBuf;
-use std::process::{Command, Stdio};
+use std::io::{self, Write as _};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
 use std::str::from_utf8;
 
 pub use camino;
 pub use semver;
 use semver::Version;
 
-pub use dependency::Dependency;
+#[cfg(feature = "builder")]
+pub use builder::BuildError;
 #[cfg(feature = "builder")]
 pub use dependency::DependencyBuilder;
+pub use dependency::{Dependency, DependencyKind, DependencySourceKind};
 pub use errors::{Error, Result};
+pub use features::{FeaturesSpec, resolve_features};
+pub use graph::DotOptions;
+pub use license::{LicenseExpression, LicenseParseError, LicenseSummary};
+pub use manifest::ManifestSpan;
+pub use merge::{MergeError, MergeOptions, MergeSummary};
+pub use partial::{PartialMetadata, PartialResult};
+pub use rebase::RebaseSummary;
+pub use section::MetadataSectionError;
 use serde::{Deserialize, Serialize};
+pub use tree::{PrefixStyle, TreeOptions};
+pub use warnings::Warning;
 
+mod batch;
+#[cfg(feature = "builder")]
+mod builder;
+mod cache;
 mod dependency;
 mod errors;
+mod executable;
+mod features;
+mod fingerprint;
+mod graph;
+mod license;
+mod manifest;
+mod merge;
+mod partial;
+mod rebase;
+mod section;
+mod sources;
+mod tree;
+mod visit;
+mod warnings;
 
 /// An "opaque" identifier for a package.
 ///
@@ -82,6 +116,90 @@ pub struct PackageId {
 	pub repr: String,
 }
 
+impl PackageId {
+	/// Build the id `wesl metadata` gives a package: `source#name@version`, or `name@version`
+	/// for a package without a source.
+	#[must_use]
+	pub fn from_name_version_source(
+		name: &str,
+		version: &Version,
+		source: Option<&str>,
+	) -> Self {
+		let repr = match source {
+			Some(source) => format!("{source}#{name}@{version}"),
+			None => format!("{name}@{version}"),
+		};
+		Self { repr }
+	}
+
+	/// Split this id into the name, version and source it was built from, the reverse of
+	/// [`Self::from_name_version_source`].
+	///
+	/// A `source#version` id, which leaves out a name equal to the last segment of the source
+	/// path, is also understood. Returns `None` if the id is not in either format.
+	#[must_use]
+	pub fn try_parse_components(&self) -> Option<(String, Version, Option<String>)> {
+		let (source, fragment) = match self.repr.rsplit_once('#') {
+			Some((source, fragment)) => (Some(source), fragment),
+			None => (None, self.repr.as_str()),
+		};
+		let (name, version) = if let Some(components) = fragment.rsplit_once('@') {
+			components
+		} else {
+			let path = source?.split('?').next()?;
+			(path.trim_end_matches('/').rsplit('/').next()?, fragment)
+		};
+		if name.is_empty() {
+			return None;
+		}
+		Some((
+			name.to_owned(),
+			version.parse().ok()?,
+			source.map(str::to_owned),
+		))
+	}
+
+	/// The underlying string representation of this id.
+	#[must_use]
+	pub fn as_str(&self) -> &str {
+		&self.repr
+	}
+}
+
+impl AsRef<str> for PackageId {
+	fn as_ref(&self) -> &str {
+		&self.repr
+	}
+}
+
+impl Borrow<str> for PackageId {
+	fn borrow(&self) -> &str {
+		&self.repr
+	}
+}
+
+impl Deref for PackageId {
+	type Target = str;
+
+	fn deref(&self) -> &Self::Target {
+		&self.repr
+	}
+}
+
+impl From<String> for PackageId {
+	fn from(repr: String) -> Self {
+		Self { repr }
+	}
+}
+
+impl From<&str> for PackageId {
+	fn from(repr: &str) -> Self {
+		Self {
+			repr: repr.to_owned(),
+		}
+	}
+}
+
 impl fmt::Display for PackageId {
 	fn fmt(
 		&self,
@@ -91,7 +209,7 @@ impl fmt::Display for PackageId {
 	}
 }
 
-/// Helpers for default metadata fields
+/// Helpers for default metadata fields.
 const fn is_null(value: &serde_json::Value) -> bool {
 	matches!(value, serde_json::Value::Null)
 }
@@ -100,25 +218,122 @@ const fn is_null(value: &serde_json::Value) -> bool {
 #[cfg_attr(feature = "builder", derive(Builder))]
 #[non_exhaustive]
 #[cfg_attr(feature = "builder", builder(pattern = "owned", setter(into)))]
-/// Starting point for metadata returned by `wesl metadata`
+/// Starting point for metadata returned by `wesl metadata`.
+#[expect(
+	clippy::partial_pub_fields,
+	reason = "the package index is an implementation detail"
+)]
 pub struct Metadata {
 	/// The package manager of this package (for getting dependency packages).
 	pub package_manager: PackageManager,
 
-	/// A list of all crates referenced by this crate (and the crate itself)
+	/// A list of all crates referenced by this crate (and the crate itself).
 	pub packages: Vec<Package>,
 
-	/// Dependencies graph
+	/// A list of all workspace members.
+	#[serde(default)]
+	#[cfg_attr(feature = "builder", builder(default))]
+	pub workspace_members: Vec<PackageId>,
+
+	/// Dependencies graph.
 	pub resolve: Option<Resolve>,
 
-	/// Target directory
+	/// Target directory.
 	pub target_directory: Utf8PathBuf,
 
-	/// The metadata format version
+	/// The metadata format version.
 	pub version: usize,
 
-	/// The directory of the root package
+	/// The directory of the root package.
 	pub root_package_directory: Utf8PathBuf,
+
+	/// Positions of the packages in `packages` by id, see [`Metadata::reindex`].
+	#[serde(skip)]
+	#[cfg_attr(feature = "builder", builder(setter(skip)))]
+	package_index: PackageIndex,
+}
+
+/// A chain of dependencies explaining why a package is part of the graph,
+/// see [`Metadata::why_duplicated`].
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct DependencyChain {
+	/// The package that is explained.
+	pub package: PackageId,
+
+	/// The ids from the root to `package`, both included, each depending on the next.
+	pub path: Vec<PackageId>,
+}
+
+/// A summary of a dependency graph, see [`Resolve::stats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct GraphStats {
+	/// The number of nodes.
+	pub nodes: usize,
+
+	/// The number of edges, counting an edge between the same two nodes once.
+	pub edges: usize,
+
+	/// The length of the longest shortest path from the root to a node,
+	/// or `None` if there is no root.
+	pub max_depth: Option<usize>,
+
+	/// The number of nodes without dependencies.
+	pub leaves: usize,
+}
+
+/// The top-level fields of the JSON form of [`Metadata`], see [`MetadataCommand::strict`].
+const METADATA_FIELDS: [&str; 7] = [
+	"package_manager",
+	"packages",
+	"workspace_members",
+	"resolve",
+	"target_directory",
+	"version",
+	"root_package_directory",
+];
+
+/// Positions of packages by id, used to look up packages without scanning.
+///
+/// It is not part of the value of [`Metadata`]: it is ignored when comparing and hashing.
+#[derive(Clone, Default)]
+struct PackageIndex {
+	/// The position of each package in `packages`.
+	positions: BTreeMap<PackageId, usize>,
+
+	/// The number of packages when the index was built, to notice added or removed packages.
+	packages: usize,
+}
+
+impl PartialEq for PackageIndex {
+	fn eq(
+		&self,
+		_other: &Self,
+	) -> bool {
+		true
+	}
+}
+
+impl Eq for PackageIndex {}
+
+impl Hash for PackageIndex {
+	fn hash<H: std::hash::Hasher>(
+		&self,
+		_state: &mut H,
+	) {
+	}
+}
+
+impl fmt::Debug for PackageIndex {
+	fn fmt(
+		&self,
+		formatter: &mut fmt::Formatter<'_>,
+	) -> fmt::Result {
+		formatter
+			.debug_struct("PackageIndex")
+			.finish_non_exhaustive()
+	}
 }
 
 /// The package manager used for getting dependencies of the WESL package.
@@ -130,14 +345,78 @@ pub enum PackageManager {
 	Cargo,
 }
 
+impl PackageManager {
+	/// The name of this package manager as in the output of `wesl metadata`.
+	#[must_use]
+	pub const fn as_str(self) -> &'static str {
+		match self {
+			Self::Npm => "Npm",
+			Self::Cargo => "Cargo",
+		}
+	}
+}
+
 impl Metadata {
+	/// Iterate over the packages.
+	pub fn iter(&self) -> std::slice::Iter<'_, Package> {
+		self.packages.iter()
+	}
+
+	/// Get the package with the given id.
+	///
+	/// This is a logarithmic lookup for metadata returned by [`MetadataCommand`].
+	/// After modifying `packages`, call [`Metadata::reindex`]. Until then, a lookup only
+	/// scans `packages` when the index points at a different package or the number of
+	/// packages changed, so a package whose id was changed in place is not found.
+	#[must_use]
+	pub fn get(
+		&self,
+		id: &PackageId,
+	) -> Option<&Package> {
+		let indexed = self
+			.package_index
+			.positions
+			.get(id)
+			.and_then(|position| self.packages.get(*position));
+		match indexed {
+			Some(package) if package.id == *id => Some(package),
+			None if self.package_index.packages == self.packages.len() => None,
+			_ => self.packages.iter().find(|package| package.id == *id),
+		}
+	}
+
+	/// Rebuild the index used by [`Metadata::get`] and by indexing with a [`PackageId`].
+	///
+	/// [`MetadataCommand`] does this after parsing, so this is only needed
+	/// after modifying `packages` or when deserializing `Metadata` directly.
+	pub fn reindex(&mut self) {
+		self.package_index = PackageIndex {
+			positions: self
+				.packages
+				.iter()
+				.enumerate()
+				.map(|(position, package)| (package.id.clone(), position))
+				.collect(),
+			packages: self.packages.len(),
+		};
+	}
+
+	/// Drop the excess capacity of `packages`, `workspace_members` and `resolve.nodes`.
+	pub fn shrink_to_fit(&mut self) {
+		self.packages.shrink_to_fit();
+		self.workspace_members.shrink_to_fit();
+		if let Some(resolve) = &mut self.resolve {
+			resolve.nodes.shrink_to_fit();
+		}
+	}
+
 	/// Get the root package of this metadata instance.
 	#[must_use]
 	pub fn root_package(&self) -> Option<&Package> {
 		if let Some(resolve) = &self.resolve {
 			// if dependencies are resolved, use `wesl`'s answer
 			let root = resolve.root.as_ref()?;
-			self.packages.iter().find(|pkg| &pkg.id == root)
+			self.get(root)
 		} else {
 			// if dependencies aren't resolved, check for a root package manually
 			let root_manifest_path = self.root_package_directory.join("wesl.toml");
@@ -146,6 +425,465 @@ impl Metadata {
 				.find(|pkg| pkg.manifest_path == root_manifest_path)
 		}
 	}
+
+	/// Keep only the packages for which `predicate` returns true.
+	///
+	/// The `resolve` graph is pruned consistently: nodes of removed packages are
+	/// dropped, edges pointing to them are removed, and `resolve.root` is cleared
+	/// if the root package was removed.
+	///
+	/// This is a fallback for when the installed `wesl` does not support
+	/// [`MetadataCommand::package`] or [`MetadataCommand::exclude`].
+	pub fn retain_packages<Predicate: FnMut(&Package) -> bool>(
+		&mut self,
+		mut predicate: Predicate,
+	) {
+		self.packages.retain(|package| predicate(package));
+		let Some(resolve) = &mut self.resolve else {
+			return;
+		};
+		let retained: BTreeSet<&PackageId> =
+			self.packages.iter().map(|package| &package.id).collect();
+		resolve.nodes.retain(|node| retained.contains(&node.id));
+		for node in &mut resolve.nodes {
+			node.dependencies
+				.retain(|dependency| retained.contains(dependency));
+			node.renamed_dependencies
+				.retain(|dependency| retained.contains(&dependency.pkg));
+		}
+		if resolve
+			.root
+			.as_ref()
+			.is_some_and(|root| !retained.contains(root))
+		{
+			resolve.root = None;
+		}
+	}
+
+	/// Extract the packages reachable from `roots` into a standalone metadata, e.g. to hand
+	/// only the dependencies of one package to a plugin.
+	///
+	/// Reachability follows the `resolve` graph, see [`Resolve::subgraph`], so unreachable
+	/// versions of a package are dropped. Without `resolve`, it follows
+	/// [`Package::dependencies`] by name, reaching every version of a dependency.
+	/// Packages, nodes and edges keep their original order, and workspace members are kept if
+	/// they are reachable.
+	///
+	/// With a single root, it becomes `resolve.root`, and `root_package_directory` becomes the
+	/// directory of its manifest. With several roots, `resolve.root` is `None` and
+	/// `root_package_directory` is unchanged.
+	#[must_use]
+	pub fn subset(
+		&self,
+		roots: &[PackageId],
+	) -> Self {
+		let mut subset = self.clone();
+		let reachable: BTreeSet<PackageId> = if let Some(resolve) = &self.resolve {
+			let subgraph = resolve.subgraph(roots);
+			let reachable = subgraph.nodes.iter().map(|node| node.id.clone()).collect();
+			subset.resolve = Some(subgraph);
+			reachable
+		} else {
+			self.reachable_by_name(roots)
+		};
+		subset.retain_packages(|package| reachable.contains(&package.id));
+		subset
+			.workspace_members
+			.retain(|member| reachable.contains(member));
+		let single_root = match roots {
+			[root] => subset.get(root).cloned(),
+			_ => None,
+		};
+		if let Some(resolve) = &mut subset.resolve {
+			resolve.root = single_root.as_ref().map(|root| root.id.clone());
+		}
+		if let Some(root) = single_root {
+			subset.root_package_directory = root.manifest_dir().to_owned();
+		}
+		subset.reindex();
+		subset
+	}
+
+	/// Remove the packages that are not reachable from `resolve.root`, or from the workspace
+	/// members if there is no root, e.g. to cache a smaller serialized form.
+	///
+	/// Reachability follows the `resolve` graph, see [`Resolve::dependency_closure`], and the
+	/// graph is pruned like by [`Self::retain_packages`]. Without `resolve`, it follows
+	/// [`Package::dependencies`] by name from the workspace members, like [`Self::subset`].
+	/// Workspace members are kept if they are reachable.
+	pub fn shrink_to_reachable(&mut self) {
+		let roots: Vec<PackageId> = match self
+			.resolve
+			.as_ref()
+			.and_then(|resolve| resolve.root.clone())
+		{
+			Some(root) => vec![root],
+			None => self.workspace_members.clone(),
+		};
+		let reachable = match &self.resolve {
+			Some(resolve) => resolve.dependency_closure(&roots),
+			None => self.reachable_by_name(&roots),
+		};
+		self.retain_packages(|package| reachable.contains(&package.id));
+		self.workspace_members
+			.retain(|member| reachable.contains(member));
+		self.reindex();
+	}
+
+	/// The ids of the packages reachable from `roots` through [`Package::dependencies`],
+	/// matching dependencies to packages by name.
+	fn reachable_by_name(
+		&self,
+		roots: &[PackageId],
+	) -> BTreeSet<PackageId> {
+		let mut by_name: BTreeMap<&str, Vec<&Package>> = BTreeMap::new();
+		for package in &self.packages {
+			by_name.entry(&package.name).or_default().push(package);
+		}
+		let mut reachable = BTreeSet::new();
+		let mut stack: Vec<&Package> = roots.iter().filter_map(|root| self.get(root)).collect();
+		while let Some(package) = stack.pop() {
+			if reachable.insert(package.id.clone()) {
+				for dependency in &package.dependencies {
+					stack.extend(by_name.get(dependency.name.as_str()).into_iter().flatten());
+				}
+			}
+		}
+		reachable
+	}
+
+	/// Get the workspace member packages of this metadata instance.
+	///
+	/// Members without a matching entry in `packages` are skipped.
+	#[must_use]
+	pub fn workspace_member_packages(&self) -> Vec<&Package> {
+		self.packages
+			.iter()
+			.filter(|package| self.workspace_members.contains(&package.id))
+			.collect()
+	}
+
+	/// Collect the authors of all packages, deduplicated.
+	#[must_use]
+	pub fn all_authors(&self) -> BTreeSet<&str> {
+		self.packages
+			.iter()
+			.flat_map(|package| package.authors.iter().map(String::as_str))
+			.collect()
+	}
+
+	/// Collect the `license` fields of all packages, deduplicated.
+	#[must_use]
+	pub fn all_licenses(&self) -> BTreeSet<&str> {
+		self.packages
+			.iter()
+			.filter_map(|package| package.license.as_deref())
+			.collect()
+	}
+
+	/// Iterate over the packages with neither a `license` nor a `license-file`.
+	pub fn packages_without_license(&self) -> impl Iterator<Item = &Package> {
+		self.packages
+			.iter()
+			.filter(|package| package.license.is_none() && package.license_file.is_none())
+	}
+
+	/// Serialize to JSON in the format of `wesl metadata`, readable by [`MetadataCommand::parse`].
+	pub fn to_json(&self) -> serde_json::Result<String> {
+		serde_json::to_string(self)
+	}
+
+	/// A short human-readable summary of this metadata for diagnostics: the number of
+	/// packages, the root package, the package manager and the target directory, one per line.
+	///
+	/// ```text
+	/// packages: 12
+	/// root: app 1.0.0
+	/// package manager: Cargo
+	/// target directory: /home/user/app/target
+	/// ```
+	#[must_use]
+	pub fn summary(&self) -> String {
+		Summary(self).to_string()
+	}
+
+	/// Serialize to indented JSON, see [`Self::to_json`].
+	pub fn to_json_pretty(&self) -> serde_json::Result<String> {
+		serde_json::to_string_pretty(self)
+	}
+
+	/// Compare with `other` like `==`, but ignoring `target_directory` and
+	/// `root_package_directory`, which differ between machines for the same graph.
+	#[must_use]
+	pub fn logical_eq(
+		&self,
+		other: &Self,
+	) -> bool {
+		self.package_manager == other.package_manager
+			&& self.packages == other.packages
+			&& self.workspace_members == other.workspace_members
+			&& self.resolve == other.resolve
+			&& self.version == other.version
+	}
+
+	/// Summarize the `resolve` graph, see [`Resolve::stats`].
+	#[must_use]
+	pub fn graph_stats(&self) -> Option<GraphStats> {
+		self.resolve.as_ref().map(Resolve::stats)
+	}
+
+	/// Iterate over the packages for which `predicate` returns true.
+	///
+	/// ```
+	/// # use wesl_metadata::Metadata;
+	/// # fn example(metadata: &Metadata) {
+	/// let unlicensed_locals: Vec<&str> = metadata
+	///     .packages_matching(|package| package.source.is_none() && package.license.is_none())
+	///     .map(|package| package.name.as_str())
+	///     .collect();
+	/// # }
+	/// ```
+	pub fn packages_matching<Predicate: Fn(&Package) -> bool>(
+		&self,
+		predicate: Predicate,
+	) -> impl Iterator<Item = &Package> {
+		self.packages
+			.iter()
+			.filter(move |package| predicate(package))
+	}
+
+	/// Iterate over the packages with `keyword` in their `keywords`, ignoring ASCII case.
+	pub fn packages_with_keyword(
+		&self,
+		keyword: &str,
+	) -> impl Iterator<Item = &Package> {
+		self.packages_matching(move |package| {
+			package
+				.keywords
+				.iter()
+				.any(|candidate| candidate.eq_ignore_ascii_case(keyword))
+		})
+	}
+
+	/// Iterate over the packages with `category` in their `categories`, ignoring ASCII case.
+	pub fn packages_with_category(
+		&self,
+		category: &str,
+	) -> impl Iterator<Item = &Package> {
+		self.packages_matching(move |package| {
+			package
+				.categories
+				.iter()
+				.any(|candidate| candidate.eq_ignore_ascii_case(category))
+		})
+	}
+
+	/// Find the package names that appear in more than one distinct version.
+	///
+	/// Each name is returned with all of its packages, sorted by version and then by id.
+	#[must_use]
+	pub fn duplicate_packages(&self) -> BTreeMap<&str, Vec<&Package>> {
+		let mut by_name: BTreeMap<&str, Vec<&Package>> = BTreeMap::new();
+		for package in &self.packages {
+			by_name.entry(&package.name).or_default().push(package);
+		}
+		by_name.retain(|_, packages| {
+			packages.sort_by(|first, second| {
+				first
+					.version
+					.cmp(&second.version)
+					.then_with(|| first.id.cmp(&second.id))
+			});
+			packages
+				.windows(2)
+				.any(|pair| matches!(pair, [first, second] if first.version != second.version))
+		});
+		by_name
+	}
+
+	/// Map every package in `resolve` to the sorted ids of the packages depending on it.
+	///
+	/// The dependencies of a node are its `renamed_dependencies`, or its `dependencies` if it
+	/// has no renamed ones. Packages without dependents map to an empty list.
+	/// Returns [`Error::MissingResolve`] without a `resolve` graph, since then the dependents
+	/// are unknown rather than absent.
+	pub fn reverse_dependencies(&self) -> Result<BTreeMap<&PackageId, Vec<&PackageId>>> {
+		let resolve = self.resolve.as_ref().ok_or(Error::MissingResolve)?;
+		let mut reverse: BTreeMap<&PackageId, BTreeSet<&PackageId>> = resolve
+			.nodes
+			.iter()
+			.map(|node| (&node.id, BTreeSet::new()))
+			.collect();
+		for node in &resolve.nodes {
+			let dependencies: Vec<&PackageId> = if node.renamed_dependencies.is_empty() {
+				node.dependencies.iter().collect()
+			} else {
+				node.renamed_dependencies
+					.iter()
+					.map(|dependency| &dependency.pkg)
+					.collect()
+			};
+			for dependency in dependencies {
+				reverse.entry(dependency).or_default().insert(&node.id);
+			}
+		}
+		Ok(reverse
+			.into_iter()
+			.map(|(id, dependents)| (id, dependents.into_iter().collect()))
+			.collect())
+	}
+
+	/// The ids of the packages depending on `id` directly or indirectly, see
+	/// [`Self::reverse_dependencies`].
+	///
+	/// `id` itself is only included if it is part of a dependency cycle.
+	pub fn transitive_reverse_dependencies(
+		&self,
+		id: &PackageId,
+	) -> Result<BTreeSet<&PackageId>> {
+		let reverse = self.reverse_dependencies()?;
+		let mut dependents = BTreeSet::new();
+		let mut stack = vec![id];
+		while let Some(current) = stack.pop() {
+			for dependent in reverse.get(current).into_iter().flatten() {
+				if dependents.insert(*dependent) {
+					stack.push(dependent);
+				}
+			}
+		}
+		Ok(dependents)
+	}
+
+	/// Explain why the package `name` is duplicated, like `cargo tree -d -i`.
+	///
+	/// For each of its packages, in the order of [`Self::duplicate_packages`], this returns one
+	/// shortest chain of dependencies from the root package, or from the workspace members if
+	/// there is no root. Every kind of dependency edge in `resolve` is followed, and packages
+	/// that cannot be reached from there, or a `name` that is not duplicated, have no chain.
+	#[must_use]
+	pub fn why_duplicated(
+		&self,
+		name: &str,
+	) -> Vec<DependencyChain> {
+		let Some(resolve) = &self.resolve else {
+			return Vec::new();
+		};
+		let Some(duplicates) = self.duplicate_packages().remove(name) else {
+			return Vec::new();
+		};
+		let nodes: BTreeMap<&PackageId, &Node> =
+			resolve.nodes.iter().map(|node| (&node.id, node)).collect();
+		let roots: Vec<&PackageId> = match &resolve.root {
+			Some(root) => vec![root],
+			None => self.workspace_members.iter().collect(),
+		};
+
+		// breadth-first, so the first parent found for an id is on a shortest path to it
+		let mut parents: BTreeMap<&PackageId, Option<&PackageId>> =
+			roots.iter().map(|root| (*root, None)).collect();
+		let mut queue: VecDeque<&PackageId> = roots.into_iter().collect();
+		while let Some(id) = queue.pop_front() {
+			for dependency in nodes
+				.get(id)
+				.into_iter()
+				.flat_map(|node| node.dependency_ids())
+			{
+				if !parents.contains_key(dependency) {
+					parents.insert(dependency, Some(id));
+					queue.push_back(dependency);
+				}
+			}
+		}
+
+		duplicates
+			.into_iter()
+			.filter(|package| parents.contains_key(&package.id))
+			.map(|package| {
+				let mut path = vec![package.id.clone()];
+				let mut current = &package.id;
+				while let Some(Some(parent)) = parents.get(current) {
+					path.push((*parent).clone());
+					current = parent;
+				}
+				path.reverse();
+				DependencyChain {
+					package: package.id.clone(),
+					path,
+				}
+			})
+			.collect()
+	}
+
+	/// Build a map from every [`PackageId`] to its [`Package`].
+	///
+	/// Unlike [`Self::get`] and indexing `Metadata` by `PackageId`, this does not depend on
+	/// [`Self::reindex`] being called after modifying `packages`, so callers doing many
+	/// lookups on modified metadata can build this map once instead. The map borrows from
+	/// `self`.
+	#[must_use]
+	pub fn index_by_id(&self) -> BTreeMap<&PackageId, &Package> {
+		self.packages
+			.iter()
+			.map(|package| (&package.id, package))
+			.collect()
+	}
+
+	/// Build a map from every [`PackageId`] to its [`Node`] in `resolve`, which is empty
+	/// without `resolve`.
+	///
+	/// Looking up nodes with [`Resolve::get`] scans `nodes` linearly, so callers doing many
+	/// lookups should build this map once instead. The map is a snapshot: it is not kept in
+	/// sync if `resolve` is modified afterwards.
+	#[must_use]
+	pub fn resolve_map(&self) -> BTreeMap<&PackageId, &Node> {
+		self.resolve
+			.iter()
+			.flatten()
+			.map(|node| (&node.id, node))
+			.collect()
+	}
+
+	/// Create `target_directory`, and any missing parents, if it does not exist yet.
+	pub fn ensure_target_directory(&self) -> io::Result<()> {
+		std::fs::create_dir_all(&self.target_directory)
+	}
+
+	/// Build a path under `target_directory`.
+	///
+	/// Only the normal components of `relative` are kept, so root, prefix, `.` and `..`
+	/// components cannot make the result escape `target_directory`.
+	#[must_use]
+	pub fn target_subdir<Relative: AsRef<Utf8Path>>(
+		&self,
+		relative: Relative,
+	) -> Utf8PathBuf {
+		let mut path = self.target_directory.clone();
+		path.extend(
+			relative
+				.as_ref()
+				.components()
+				.filter(|component| matches!(component, Utf8Component::Normal(_))),
+		);
+		path
+	}
+}
+
+impl IntoIterator for Metadata {
+	type Item = Package;
+	type IntoIter = std::vec::IntoIter<Package>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.packages.into_iter()
+	}
+}
+
+impl<'item> IntoIterator for &'item Metadata {
+	type Item = &'item Package;
+	type IntoIter = std::slice::Iter<'item, Package>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.packages.iter()
+	}
 }
 
 impl<'item> std::ops::Index<&'item PackageId> for Metadata {
@@ -155,47 +893,265 @@ impl<'item> std::ops::Index<&'item PackageId> for Metadata {
 		&self,
 		index: &'item PackageId,
 	) -> &Self::Output {
-		self.packages
-			.iter()
-			.find(|package| package.id == *index)
+		self.get(index)
 			.unwrap_or_else(|| panic!("no package with this id: {index:?}"))
 	}
 }
 
+/// The [`Display`](fmt::Display) form of [`Metadata::summary`].
+struct Summary<'metadata>(&'metadata Metadata);
+
+impl fmt::Display for Summary<'_> {
+	fn fmt(
+		&self,
+		formatter: &mut fmt::Formatter<'_>,
+	) -> fmt::Result {
+		let Self(metadata) = self;
+		writeln!(formatter, "packages: {}", metadata.packages.len())?;
+		match metadata.root_package() {
+			Some(root) => writeln!(formatter, "root: {} {}", root.name, root.version)?,
+			None => writeln!(formatter, "root: none")?,
+		}
+		writeln!(
+			formatter,
+			"package manager: {}",
+			metadata.package_manager.as_str()
+		)?;
+		write!(formatter, "target directory: {}", metadata.target_directory)
+	}
+}
+
+impl TryFrom<&[u8]> for Metadata {
+	type Error = Error;
+
+	/// Parse the JSON output of `wesl metadata` given as bytes, see [`MetadataCommand::parse`].
+	fn try_from(bytes: &[u8]) -> Result<Self> {
+		MetadataCommand::parse(from_utf8(bytes)?)
+	}
+}
+
+impl TryFrom<serde_json::Value> for Metadata {
+	type Error = Error;
+
+	fn try_from(value: serde_json::Value) -> Result<Self> {
+		let mut metadata: Self = serde_json::from_value(value)?;
+		metadata.reindex();
+		Ok(metadata)
+	}
+}
+
+impl TryFrom<Metadata> for serde_json::Value {
+	type Error = Error;
+
+	fn try_from(metadata: Metadata) -> Result<Self> {
+		Ok(serde_json::to_value(metadata)?)
+	}
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "builder", derive(Builder))]
 #[non_exhaustive]
 #[cfg_attr(feature = "builder", builder(pattern = "owned", setter(into)))]
-/// A dependency graph
+/// A dependency graph.
 pub struct Resolve {
-	/// Nodes in a dependencies graph
+	/// Nodes in a dependencies graph.
 	pub nodes: Vec<Node>,
 
 	/// The crate for which the metadata was read.
 	pub root: Option<PackageId>,
 }
 
-impl<'item> std::ops::Index<&'item PackageId> for Resolve {
-	type Output = Node;
+impl Resolve {
+	/// Iterate over the nodes.
+	pub fn iter(&self) -> std::slice::Iter<'_, Node> {
+		self.nodes.iter()
+	}
 
-	fn index(
+	/// Get the node with the given id, without panicking like indexing does.
+	#[must_use]
+	pub fn get(
 		&self,
-		index: &'item PackageId,
-	) -> &Self::Output {
+		id: &PackageId,
+	) -> Option<&Node> {
+		self.nodes.iter().find(|node| node.id == *id)
+	}
+
+	/// The node of the `root` package, if there is one.
+	#[must_use]
+	pub fn root_node(&self) -> Option<&Node> {
+		self.get(self.root.as_ref()?)
+	}
+
+	/// Iterate over the nodes that have no dependencies.
+	pub fn leaves(&self) -> impl Iterator<Item = &Node> {
 		self.nodes
 			.iter()
-			.find(|package| package.id == *index)
-			.unwrap_or_else(|| panic!("no Node with this id: {index:?}"))
+			.filter(|node| node.dependency_ids().next().is_none())
+	}
+
+	/// The ids of the nodes that no other node depends on, in the order of [`Self::nodes`].
+	///
+	/// Unlike the single [`Self::root`], these are the true roots of the graph, e.g. every
+	/// member of a virtual workspace that no other member depends on.
+	#[must_use]
+	pub fn roots(&self) -> Vec<&PackageId> {
+		let dependencies: BTreeSet<&PackageId> =
+			self.nodes.iter().flat_map(Node::dependency_ids).collect();
+		self.nodes
+			.iter()
+			.map(|node| &node.id)
+			.filter(|id| !dependencies.contains(id))
+			.collect()
+	}
+
+	/// Summarize the size and shape of this graph.
+	#[must_use]
+	pub fn stats(&self) -> GraphStats {
+		let nodes: BTreeMap<&PackageId, &Node> =
+			self.nodes.iter().map(|node| (&node.id, node)).collect();
+		let edges = self
+			.nodes
+			.iter()
+			.map(|node| node.dependency_ids().collect::<BTreeSet<_>>().len())
+			.sum();
+
+		let max_depth = self.root.as_ref().map(|root| {
+			let mut depths = BTreeMap::from([(root, 0)]);
+			let mut queue = VecDeque::from([(root, 0)]);
+			let mut max_depth = 0;
+			while let Some((id, depth)) = queue.pop_front() {
+				max_depth = max_depth.max(depth);
+				for dependency in nodes
+					.get(id)
+					.into_iter()
+					.flat_map(|node| node.dependency_ids())
+				{
+					if !depths.contains_key(dependency) {
+						depths.insert(dependency, depth + 1);
+						queue.push_back((dependency, depth + 1));
+					}
+				}
+			}
+			max_depth
+		});
+
+		GraphStats {
+			nodes: self.nodes.len(),
+			edges,
+			max_depth,
+			leaves: self.leaves().count(),
+		}
+	}
+
+	/// The ids of the given roots and of every package reachable from them, e.g. of the
+	/// packages used by the workspace members.
+	///
+	/// Only packages that have a node in this graph are included, so a root or a dependency
+	/// without one is skipped. Cycles are followed only once.
+	#[must_use]
+	pub fn dependency_closure<'id, Roots: IntoIterator<Item = &'id PackageId>>(
+		&self,
+		roots: Roots,
+	) -> BTreeSet<PackageId> {
+		let nodes: BTreeMap<&PackageId, &Node> =
+			self.nodes.iter().map(|node| (&node.id, node)).collect();
+		let mut reachable = BTreeSet::new();
+		let mut stack: Vec<&PackageId> = roots.into_iter().collect();
+		while let Some(id) = stack.pop() {
+			let Some(node) = nodes.get(id) else {
+				continue;
+			};
+			if reachable.insert(id.clone()) {
+				stack.extend(node.dependency_ids());
+			}
+		}
+		reachable
+	}
+
+	/// Build the part of this graph reachable from `roots`.
+	///
+	/// The returned `Resolve` contains the given roots and every node reachable from them,
+	/// in their original order. Edges to nodes outside the subgraph are removed, and `root`
+	/// is kept only if it is part of the subgraph.
+	#[must_use]
+	pub fn subgraph(
+		&self,
+		roots: &[PackageId],
+	) -> Self {
+		let reachable = self.dependency_closure(roots);
+		let nodes = self
+			.nodes
+			.iter()
+			.filter(|node| reachable.contains(&node.id))
+			.map(|node| Node {
+				id: node.id.clone(),
+				renamed_dependencies: node
+					.renamed_dependencies
+					.iter()
+					.filter(|dependency| reachable.contains(&dependency.pkg))
+					.cloned()
+					.collect(),
+				dependencies: node
+					.dependencies
+					.iter()
+					.filter(|dependency| reachable.contains(*dependency))
+					.cloned()
+					.collect(),
+			})
+			.collect();
+		Self {
+			nodes,
+			root: self.root.clone().filter(|root| reachable.contains(root)),
+		}
+	}
+}
+
+impl IntoIterator for Resolve {
+	type Item = Node;
+	type IntoIter = std::vec::IntoIter<Node>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.nodes.into_iter()
+	}
+}
+
+impl<'item> IntoIterator for &'item Resolve {
+	type Item = &'item Node;
+	type IntoIter = std::slice::Iter<'item, Node>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.nodes.iter()
+	}
+}
+
+impl<'item> std::ops::Index<&'item PackageId> for Resolve {
+	type Output = Node;
+
+	fn index(
+		&self,
+		index: &'item PackageId,
+	) -> &Self::Output {
+		self.get(index).unwrap_or_else(|| {
+			panic!("no Node with this id: {index:?}, use `Resolve::get` to handle missing nodes")
+		})
 	}
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
+#[serde(from = "RawNode")]
 #[cfg_attr(feature = "builder", derive(Builder))]
 #[non_exhaustive]
-#[cfg_attr(feature = "builder", builder(pattern = "owned", setter(into)))]
-/// A node in a dependencies graph
+#[cfg_attr(
+	feature = "builder",
+	builder(pattern = "owned", setter(into), build_fn(validate = "Self::validate"))
+)]
+/// A node in a dependencies graph.
+///
+/// Structured dependencies are read from `renamed_dependencies`, or from the legacy `deps`
+/// key of older `wesl` versions if it is missing. They are always written as
+/// `renamed_dependencies`.
 pub struct Node {
-	/// An opaque identifier for a package
+	/// An opaque identifier for a package.
 	pub id: PackageId,
 
 	/// Dependencies in a structured format.
@@ -209,55 +1165,168 @@ pub struct Node {
 	pub dependencies: Vec<PackageId>,
 }
 
+/// The JSON form of a [`Node`] accepted when deserializing.
+#[derive(Deserialize)]
+struct RawNode {
+	id: PackageId,
+	renamed_dependencies: Option<Vec<NodeDependency>>,
+	/// Structured dependencies from older `wesl` versions, whose extra fields are ignored.
+	deps: Option<Vec<NodeDependency>>,
+	dependencies: Vec<PackageId>,
+}
+
+impl From<RawNode> for Node {
+	fn from(raw: RawNode) -> Self {
+		Self {
+			id: raw.id,
+			renamed_dependencies: raw.renamed_dependencies.or(raw.deps).unwrap_or_default(),
+			dependencies: raw.dependencies,
+		}
+	}
+}
+
+impl Node {
+	/// Iterate over the ids in `dependencies`.
+	pub fn iter(&self) -> std::slice::Iter<'_, PackageId> {
+		self.dependencies.iter()
+	}
+
+	/// Find the renamed dependency imported as `name`.
+	#[must_use]
+	pub fn renamed_dep_by_name(
+		&self,
+		name: &str,
+	) -> Option<&NodeDependency> {
+		self.renamed_dependencies
+			.iter()
+			.find(|dependency| dependency.name == name)
+	}
+
+	/// Find the dependency on the package `id` in `renamed_dependencies`.
+	#[must_use]
+	pub fn dep_by_pkg_id(
+		&self,
+		id: &PackageId,
+	) -> Option<&NodeDependency> {
+		self.renamed_dependencies
+			.iter()
+			.find(|dependency| dependency.pkg == *id)
+	}
+
+	/// Returns true if this node depends on the package `id`, either in `dependencies` or
+	/// renamed in `renamed_dependencies`.
+	#[must_use]
+	pub fn depends_on(
+		&self,
+		id: &PackageId,
+	) -> bool {
+		self.dependency_ids().any(|dependency| dependency == id)
+	}
+
+	/// Returns true if this node is the `root` of `resolve`.
+	#[must_use]
+	pub fn is_root(
+		&self,
+		resolve: &Resolve,
+	) -> bool {
+		resolve.root.as_ref() == Some(&self.id)
+	}
+
+	/// Iterate over the ids of both plain and renamed dependencies of this node.
+	fn dependency_ids(&self) -> impl Iterator<Item = &PackageId> {
+		self.dependencies.iter().chain(
+			self.renamed_dependencies
+				.iter()
+				.map(|dependency| &dependency.pkg),
+		)
+	}
+}
+
+impl IntoIterator for Node {
+	type Item = PackageId;
+	type IntoIter = std::vec::IntoIter<PackageId>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.dependencies.into_iter()
+	}
+}
+
+impl<'item> IntoIterator for &'item Node {
+	type Item = &'item PackageId;
+	type IntoIter = std::slice::Iter<'item, PackageId>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.dependencies.iter()
+	}
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "builder", derive(Builder))]
 #[non_exhaustive]
 #[cfg_attr(feature = "builder", builder(pattern = "owned", setter(into)))]
-/// A dependency in a node
+/// A dependency in a node.
 pub struct NodeDependency {
 	/// The name of the dependency's library target.
 	/// If the crate was renamed, it is the new name.
 	pub name: String,
 
-	/// Package ID (opaque unique identifier)
+	/// Package ID (opaque unique identifier).
 	pub pkg: PackageId,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "builder", derive(Builder))]
 #[non_exhaustive]
-#[cfg_attr(feature = "builder", builder(pattern = "owned", setter(into)))]
-/// One or more crates described by a single `wesl.toml`
+#[cfg_attr(
+	feature = "builder",
+	builder(pattern = "owned", setter(into), build_fn(validate = "Self::validate"))
+)]
+/// One or more crates described by a single `wesl.toml`.
 ///
 /// Each [`target`][Package::targets] of a `Package` will be built as a crate.
 /// For more information, see <https://doc.rust-lang.org/book/ch07-01-packages-and-crates.html>.
+///
+/// Equality and hashing compare every field, including the free-form `metadata`, whose
+/// objects compare and hash the same regardless of the order of their keys. Packages are
+/// ordered by [`Package::sort_key`], and packages with the same key but different contents,
+/// which only happens when mixing metadata from different sources, by their JSON form.
 pub struct Package {
-	/// The [`name` field](https://github.com/wgsl-tooling-wg/wesl-spec/pull/136) as given in the `wesl.toml`
+	/// The [`name` field](https://github.com/wgsl-tooling-wg/wesl-spec/pull/136) as given in the `wesl.toml`.
 	pub name: String,
 
-	/// The [`version` field](https://github.com/wgsl-tooling-wg/wesl-spec/pull/136) as given in the `wesl.toml`
+	/// The [`version` field](https://github.com/wgsl-tooling-wg/wesl-spec/pull/136) as given in the `wesl.toml`.
 	pub version: Version,
 
-	/// The [`authors` field](https://github.com/wgsl-tooling-wg/wesl-spec/pull/136) as given in the `wesl.toml`
-	#[serde(default)]
+	/// The [`authors` field](https://github.com/wgsl-tooling-wg/wesl-spec/pull/136) as given in the `wesl.toml`.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
 	#[cfg_attr(feature = "builder", builder(default))]
 	pub authors: Vec<String>,
 
-	/// An opaque identifier for a package
+	/// An opaque identifier for a package.
 	pub id: PackageId,
 
 	/// The source of the package, e.g. crates.io or `None` for local projects.
 	pub source: Option<Source>,
 
-	/// The [`description` field](https://github.com/wgsl-tooling-wg/wesl-spec/pull/136) as given in the `wesl.toml`
+	/// The [`description` field](https://github.com/wgsl-tooling-wg/wesl-spec/pull/136) as given in the `wesl.toml`.
 	#[cfg_attr(feature = "builder", builder(default))]
 	pub description: Option<String>,
 
-	/// List of dependencies of this particular package
+	/// List of dependencies of this particular package.
 	#[cfg_attr(feature = "builder", builder(default))]
 	pub dependencies: Vec<Dependency>,
 
-	/// The [`license` field](https://github.com/wgsl-tooling-wg/wesl-spec/pull/136) as given in the `wesl.toml`
+	/// Targets provided by the package.
+	#[serde(default)]
+	#[cfg_attr(feature = "builder", builder(default))]
+	pub targets: Vec<Target>,
+
+	/// Features provided by the package, mapped to the features and dependencies they enable.
+	#[serde(default)]
+	#[cfg_attr(feature = "builder", builder(default))]
+	pub features: BTreeMap<String, Vec<String>>,
+
+	/// The [`license` field](https://github.com/wgsl-tooling-wg/wesl-spec/pull/136) as given in the `wesl.toml`.
 	#[cfg_attr(feature = "builder", builder(default))]
 	pub license: Option<String>,
 
@@ -267,24 +1336,24 @@ pub struct Package {
 	#[cfg_attr(feature = "builder", builder(default))]
 	pub license_file: Option<Utf8PathBuf>,
 
-	/// Path containing the `wesl.toml`
+	/// Path containing the `wesl.toml`.
 	pub manifest_path: Utf8PathBuf,
 
-	/// The [`categories` field](https://github.com/wgsl-tooling-wg/wesl-spec/pull/136) as given in the `wesl.toml`
-	#[serde(default)]
+	/// The [`categories` field](https://github.com/wgsl-tooling-wg/wesl-spec/pull/136) as given in the `wesl.toml`.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
 	#[cfg_attr(feature = "builder", builder(default))]
 	pub categories: Vec<String>,
 
-	/// The [`keywords` field](https://github.com/wgsl-tooling-wg/wesl-spec/pull/136) as given in the `wesl.toml`
-	#[serde(default)]
+	/// The [`keywords` field](https://github.com/wgsl-tooling-wg/wesl-spec/pull/136) as given in the `wesl.toml`.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
 	#[cfg_attr(feature = "builder", builder(default))]
 	pub keywords: Vec<String>,
 
-	/// The [`readme` field](https://github.com/wgsl-tooling-wg/wesl-spec/pull/136) as given in the `wesl.toml`
+	/// The [`readme` field](https://github.com/wgsl-tooling-wg/wesl-spec/pull/136) as given in the `wesl.toml`.
 	#[cfg_attr(feature = "builder", builder(default))]
 	pub readme: Option<Utf8PathBuf>,
 
-	/// The [`repository` URL](https://github.com/wgsl-tooling-wg/wesl-spec/pull/136) as given in the `wesl.toml`
+	/// The [`repository` URL](https://github.com/wgsl-tooling-wg/wesl-spec/pull/136) as given in the `wesl.toml`.
 	// can't use `url::Url` because that requires a more recent stable compiler
 	#[cfg_attr(feature = "builder", builder(default))]
 	pub repository: Option<String>,
@@ -303,6 +1372,24 @@ pub struct Package {
 	#[cfg_attr(feature = "builder", builder(default))]
 	pub edition: Edition,
 
+	/// The name of the entry point to use by default, from the `default-entry` field in the
+	/// `wesl.toml`. See [`Package::entry_point`].
+	#[serde(default, alias = "default-entry")]
+	#[cfg_attr(feature = "builder", builder(default))]
+	pub default_entry_point: Option<String>,
+
+	/// The [`include` field](https://github.com/wgsl-tooling-wg/wesl-spec/pull/136) as given in the `wesl.toml`,
+	/// globs of the files that belong to the package, see [`Package::source_files`].
+	#[serde(default)]
+	#[cfg_attr(feature = "builder", builder(default))]
+	pub include: Vec<String>,
+
+	/// The [`exclude` field](https://github.com/wgsl-tooling-wg/wesl-spec/pull/136) as given in the `wesl.toml`,
+	/// globs of the files that do not belong to the package, see [`Package::source_files`].
+	#[serde(default)]
+	#[cfg_attr(feature = "builder", builder(default))]
+	pub exclude: Vec<String>,
+
 	/// Contents of the free form [`package.metadata` section](https://github.com/wgsl-tooling-wg/wesl-spec/pull/136).
 	///
 	/// This contents can be serialized to a struct using serde:
@@ -351,28 +1438,167 @@ impl PackageBuilder {
 	}
 }
 
+impl PartialOrd for Package {
+	fn partial_cmp(
+		&self,
+		other: &Self,
+	) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for Package {
+	fn cmp(
+		&self,
+		other: &Self,
+	) -> Ordering {
+		self.sort_key().cmp(&other.sort_key()).then_with(|| {
+			if self == other {
+				Ordering::Equal
+			} else {
+				// unequal packages have different JSON forms, as every field is serialized
+				serde_json::to_string(self)
+					.ok()
+					.cmp(&serde_json::to_string(other).ok())
+			}
+		})
+	}
+}
+
 impl Package {
-	/// Full path to the license file if one is present in the manifest
+	/// The key packages are ordered by: the name, then the version, then the id.
 	#[must_use]
-	pub fn license_file(&self) -> Option<Utf8PathBuf> {
-		self.license_file.as_ref().map(|file| {
-			self.manifest_path
-				.parent()
-				.unwrap_or(&self.manifest_path)
-				.join(file)
+	pub fn sort_key(&self) -> (&str, &Version, &PackageId) {
+		(&self.name, &self.version, &self.id)
+	}
+
+	/// Deserialize the free form [`metadata`](Package::metadata) into `T`.
+	///
+	/// If there is no metadata, `T` is deserialized from `null`, so types with only optional
+	/// fields still work.
+	pub fn metadata_as<T: serde::de::DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+		T::deserialize(&self.metadata)
+	}
+
+	/// Split each of the `authors` into a name and an optional email, from the usual
+	/// `Name <email>` form.
+	///
+	/// An author without an email, or with malformed angle brackets, is returned whole as
+	/// the name.
+	#[must_use]
+	pub fn authors_parsed(&self) -> Vec<(String, Option<String>)> {
+		self.authors
+			.iter()
+			.map(|author| {
+				let author = author.trim();
+				author
+					.strip_suffix('>')
+					.and_then(|rest| rest.rsplit_once('<'))
+					.filter(|(name, email)| {
+						!email.is_empty()
+							&& !email.contains(['<', '>'])
+							&& !name.contains(['<', '>'])
+					})
+					.map_or_else(
+						|| (author.to_owned(), None),
+						|(name, email)| (name.trim().to_owned(), Some(email.trim().to_owned())),
+					)
+			})
+			.collect()
+	}
+
+	/// Find the dependency on the package `name`, or imported under the alias `name`.
+	///
+	/// See [`Self::dependency_by_effective_name`] to ignore the package name of renamed
+	/// dependencies.
+	#[must_use]
+	pub fn dependency_by_name(
+		&self,
+		name: &str,
+	) -> Option<&Dependency> {
+		self.dependencies.iter().find(|dependency| {
+			dependency.name == name || dependency.rename.as_deref() == Some(name)
 		})
 	}
 
-	/// Full path to the readme file if one is present in the manifest
+	/// Find the dependency imported under `name`, see [`Dependency::effective_name`].
 	#[must_use]
-	pub fn readme(&self) -> Option<Utf8PathBuf> {
-		self.readme.as_ref().map(|file| {
-			self.manifest_path
-				.parent()
-				.unwrap_or(&self.manifest_path)
-				.join(file)
+	pub fn dependency_by_effective_name(
+		&self,
+		name: &str,
+	) -> Option<&Dependency> {
+		self.dependencies
+			.iter()
+			.find(|dependency| dependency.effective_name() == name)
+	}
+
+	/// Returns true if `name` is a feature of this package, either from its `features` table or
+	/// implicitly from an optional dependency, e.g. to check the `required_features` of a
+	/// [`Target`].
+	///
+	/// Like in Cargo, an optional dependency referenced as `dep:<name>` in any feature has no
+	/// implicit feature.
+	#[must_use]
+	pub fn has_feature(
+		&self,
+		name: &str,
+	) -> bool {
+		if self.features.contains_key(name) {
+			return true;
+		}
+		let is_optional_dependency = self
+			.dependencies
+			.iter()
+			.any(|dependency| dependency.optional && dependency.effective_name() == name);
+		is_optional_dependency
+			&& !self
+				.features
+				.values()
+				.flatten()
+				.any(|value| value.strip_prefix("dep:") == Some(name))
+	}
+
+	/// The name of the entry point of this package.
+	///
+	/// This is `default_entry_point` if set, or else the name of the target named like the
+	/// package, if there is one.
+	#[must_use]
+	pub fn entry_point(&self) -> Option<&str> {
+		self.default_entry_point.as_deref().or_else(|| {
+			self.targets
+				.iter()
+				.find(|target| target.name == self.name)
+				.map(|target| target.name.as_str())
 		})
 	}
+
+	/// The directory containing the `wesl.toml`.
+	///
+	/// This is the parent of `manifest_path`, or `manifest_path` itself if it has no parent.
+	#[must_use]
+	pub fn manifest_dir(&self) -> &Utf8Path {
+		self.manifest_path.parent().unwrap_or(&self.manifest_path)
+	}
+
+	/// Full path to the license file if one is present in the manifest.
+	///
+	/// The `license_file` field is relative to [`Self::manifest_dir`], which this joins it to.
+	#[must_use]
+	pub fn license_file(&self) -> Option<Utf8PathBuf> {
+		self.license_file
+			.as_ref()
+			.map(|file| self.manifest_dir().join(file))
+	}
+
+	/// Full path to the readme file if one is present in the manifest.
+	///
+	/// The `readme` field is relative to [`Self::manifest_dir`], which this joins it to.
+	#[must_use]
+	pub fn readme(&self) -> Option<Utf8PathBuf> {
+		self.readme
+			.as_ref()
+			.map(|file| self.manifest_dir().join(file))
+	}
 }
 
 /// The source of a package such as crates.io or npmjs.com.
@@ -413,11 +1639,21 @@ impl fmt::Display for Source {
 #[cfg_attr(feature = "builder", derive(Builder))]
 #[cfg_attr(feature = "builder", builder(pattern = "owned", setter(into)))]
 #[non_exhaustive]
-/// A single target (lib, bin, example, ...) provided by a crate
+/// A single target (lib, bin, example, ...) provided by a crate.
 pub struct Target {
-	/// Name as given in the `wesl.toml` or generated from the file name
+	/// Name as given in the `wesl.toml` or generated from the file name.
 	pub name: String,
 
+	/// Kind of target (`"lib"`, `"bin"`, `"example"`, ...).
+	#[serde(default)]
+	#[cfg_attr(feature = "builder", builder(default))]
+	pub kind: Vec<String>,
+
+	/// The artifact types this target produces, as given in the `wesl.toml`.
+	#[serde(default)]
+	#[cfg_attr(feature = "builder", builder(default))]
+	pub crate_types: Vec<String>,
+
 	#[serde(default)]
 	#[cfg_attr(feature = "builder", builder(default))]
 	#[serde(rename = "required-features")]
@@ -425,10 +1661,10 @@ pub struct Target {
 	/// It doesn't apply to `lib` targets.
 	pub required_features: Vec<String>,
 
-	/// Path to the main source file of the target
+	/// Path to the main source file of the target.
 	pub src_path: Utf8PathBuf,
 
-	/// Rust edition for this target
+	/// Rust edition for this target.
 	#[serde(default)]
 	#[cfg_attr(feature = "builder", builder(default))]
 	pub edition: Edition,
@@ -456,24 +1692,95 @@ pub struct Target {
 	pub doc: bool,
 }
 
-/// The WESL edition
+impl Target {
+	fn is_kind(
+		&self,
+		name: &str,
+	) -> bool {
+		self.kind.iter().any(|kind| kind == name)
+	}
+
+	/// Return true if this target is of kind `"lib"`.
+	#[must_use]
+	pub fn is_lib(&self) -> bool {
+		self.is_kind("lib")
+	}
+
+	/// Return true if this target is of kind `"bin"`.
+	#[must_use]
+	pub fn is_bin(&self) -> bool {
+		self.is_kind("bin")
+	}
+
+	/// Return true if this target is of kind `"example"`.
+	#[must_use]
+	pub fn is_example(&self) -> bool {
+		self.is_kind("example")
+	}
+
+	/// Predict where the transpiled output of this target lands when built with `profile`.
+	///
+	/// This is `<target_directory>/<profile>/<name>.wgsl` for `lib` and `bin` targets, and
+	/// `<target_directory>/<profile>/examples/<name>.wgsl` for `example` targets.
+	/// The first kind of the target decides, and `None` is returned if it is not one of those.
+	#[must_use]
+	pub fn expected_artifact_path(
+		&self,
+		metadata: &Metadata,
+		profile: &str,
+	) -> Option<Utf8PathBuf> {
+		let directory = metadata.target_directory.join(profile);
+		let directory = match self.kind.first()?.as_str() {
+			"lib" | "bin" => directory,
+			"example" => directory.join("examples"),
+			_ => return None,
+		};
+		Some(directory.join(format!("{}.wgsl", self.name)))
+	}
+}
+
+/// The WESL edition.
 ///
 /// As of writing this comment rust editions 2027 and 2030 are not actually a thing yet but are parsed nonetheless for future proofing.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[non_exhaustive]
 #[derive(Default)]
 pub enum Edition {
-	/// WGSL
+	/// WGSL.
 	#[serde(rename = "WGSL")]
 	#[default]
 	Wgsl,
-	/// WESL
+	/// WESL.
 	#[serde(rename = "WESL")]
 	WeslUnstable2025,
 }
 
 impl Edition {
-	/// Return the string representation of the edition
+	/// Every known edition, oldest first.
+	#[must_use]
+	pub const fn all() -> &'static [Self] {
+		&[Self::Wgsl, Self::WeslUnstable2025]
+	}
+
+	/// The year of this edition, or `None` for [`Edition::Wgsl`], which has no year.
+	#[must_use]
+	pub const fn year(self) -> Option<u16> {
+		match self {
+			Self::Wgsl => None,
+			Self::WeslUnstable2025 => Some(2025),
+		}
+	}
+
+	/// The edition of `year`, the reverse of [`Self::year`].
+	#[must_use]
+	pub fn try_from_year(year: u16) -> Option<Self> {
+		Self::all()
+			.iter()
+			.copied()
+			.find(|edition| edition.year() == Some(year))
+	}
+
+	/// Return the string representation of the edition.
 	#[must_use]
 	pub const fn as_str(self) -> &'static str {
 		match self {
@@ -496,15 +1803,45 @@ const fn default_true() -> bool {
 	true
 }
 
+/// Whether `wesl` colors its output, see [`MetadataCommand::color`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ColorChoice {
+	/// Color when the output is a terminal, passed as `--color auto`.
+	#[default]
+	Auto,
+	/// Always color, passed as `--color always`.
+	Always,
+	/// Never color, passed as `--color never`.
+	Never,
+}
+
+impl ColorChoice {
+	/// The value of the `--color` flag.
+	#[must_use]
+	pub const fn as_str(self) -> &'static str {
+		match self {
+			Self::Auto => "auto",
+			Self::Always => "always",
+			Self::Never => "never",
+		}
+	}
+}
+
 /// A builder for configuring `wesl metadata` invocation.
 #[derive(Debug, Clone, Default)]
 pub struct MetadataCommand {
 	/// Path to `wesl` executable. If not set, this will use the
 	/// the `$WESL` environment variable, and if that is not set, will
 	/// simply be `wesl`.
+	///
+	/// A path containing a separator must be an executable file, otherwise it is searched in
+	/// `PATH`. On Windows, `.exe`, `.cmd` and `.bat` are appended to a path without one of
+	/// these extensions, and the path itself is only tried after them if it has another
+	/// extension. If none of the candidates exist, `exec` returns
+	/// [`Error::WeslNotFound`] without running anything.
 	wesl_path: Option<PathBuf>,
 
-	/// Path to `wesl.toml`
+	/// Path to `wesl.toml`.
 	manifest_path: Option<PathBuf>,
 
 	/// Current directory of the `wesl metadata` process.
@@ -513,6 +1850,27 @@ pub struct MetadataCommand {
 	/// Output information only about the root package and don't fetch dependencies.
 	no_dependencies: bool,
 
+	/// The metadata format version to request, passed as `--format-version <version>`.
+	format_version: Option<u32>,
+
+	/// Require `wesl.lock` to be up to date, passed as `--locked`.
+	locked: bool,
+
+	/// Run without accessing the network, passed as `--offline`.
+	offline: bool,
+
+	/// Both `locked` and `offline`, passed as `--frozen`.
+	frozen: bool,
+
+	/// Whether `wesl` colors its output, passed as `--color <choice>`.
+	color: Option<ColorChoice>,
+
+	/// Package specs to select, each passed as `--package <spec>`.
+	packages: Vec<String>,
+
+	/// Package specs to exclude, each passed as `--exclude <spec>`.
+	excludes: Vec<String>,
+
 	/// Arbitrary command line flags to pass to `wesl`. These will be added
 	/// to the end of the command line invocation.
 	other_options: Vec<String>,
@@ -522,8 +1880,21 @@ pub struct MetadataCommand {
 	/// calling environment, overriding any which clash.
 	env: BTreeMap<OsString, Option<OsString>>,
 
-	/// Show stderr
+	/// Start `wesl` with an empty environment instead of inheriting the calling one.
+	/// Variables from `env` are still applied on top.
+	env_clear: bool,
+
+	/// Show stderr.
 	verbose: bool,
+
+	/// Leave checking that `manifest_path` exists to `wesl`.
+	skip_manifest_check: bool,
+
+	/// Reject unknown top-level fields in the output.
+	strict: bool,
+
+	/// Parse the output of a failed `wesl metadata` in [`Self::exec_partial`].
+	allow_partial: bool,
 }
 
 impl MetadataCommand {
@@ -543,7 +1914,9 @@ impl MetadataCommand {
 		self.wesl_path = Some(path.into());
 		self
 	}
-	/// Path to `wesl.toml`
+	/// Path to `wesl.toml`, or to the directory containing it.
+	///
+	/// A directory, relative to [`Self::current_dir`] if set, gets `wesl.toml` appended.
 	pub fn manifest_path<Pathish: Into<PathBuf>>(
 		&mut self,
 		path: Pathish,
@@ -551,6 +1924,24 @@ impl MetadataCommand {
 		self.manifest_path = Some(path.into());
 		self
 	}
+	/// Set [`Self::manifest_path`] from the `WESL_MANIFEST_PATH` environment variable, e.g. as
+	/// set by a CI system.
+	///
+	/// This does nothing if the variable is not set.
+	pub fn manifest_path_from_env(&mut self) -> &mut Self {
+		self.manifest_path_from_variable(env::var_os("WESL_MANIFEST_PATH"))
+	}
+
+	/// Set [`Self::manifest_path`] to the value of `WESL_MANIFEST_PATH`, if it is set.
+	fn manifest_path_from_variable(
+		&mut self,
+		variable: Option<OsString>,
+	) -> &mut Self {
+		if let Some(path) = variable {
+			self.manifest_path(path);
+		}
+		self
+	}
 	/// Current directory of the `wesl metadata` process.
 	pub fn current_dir<Pathish: Into<PathBuf>>(
 		&mut self,
@@ -560,49 +1951,158 @@ impl MetadataCommand {
 		self
 	}
 	/// Output information only about the root package and don't fetch dependencies.
+	#[deprecated(note = "use `set_no_dependencies(true)` instead")]
 	pub const fn no_dependencies(&mut self) -> &mut Self {
-		self.no_dependencies = true;
-		self
+		self.set_no_dependencies(true)
 	}
 
-	/// Arbitrary command line flags to pass to `wesl`.
-	/// These will be added to the end of the command line invocation.
-	pub fn other_options<Options: Into<Vec<String>>>(
+	/// Set whether to output information only about the root package and not fetch dependencies.
+	pub const fn set_no_dependencies(
 		&mut self,
-		options: Options,
+		no_dependencies: bool,
 	) -> &mut Self {
-		self.other_options = options.into();
+		self.no_dependencies = no_dependencies;
 		self
 	}
 
-	/// Arbitrary environment variables to set when running `wesl`.
-	/// These will be merged into the calling environment, overriding any which clash.
-	///
-	/// Some examples of when you may want to use this:
-	/// 1. Setting cargo config values without needing a .cargo/config.toml file, e.g. to set
-	///    `CARGO_NET_GIT_FETCH_WITH_CLI=true`
-	/// 2. To specify a custom path to RUSTC if your rust toolchain components aren't laid out in
-	///    the way cargo expects by default.
+	/// Returns a copy of this command that fetches dependencies, keeping all other settings.
+	#[must_use]
+	pub fn clone_with_dependencies(&self) -> Self {
+		let mut command = self.clone();
+		command.no_dependencies = false;
+		command
+	}
+
+	/// Request a specific metadata format version with `--format-version`.
 	///
-	/// ```no_run
-	/// # use wesl_metadata::MetadataCommand;
-	/// MetadataCommand::new()
-	///     .env("OUT_DIR", "example/value")
-	///     // ...
-	///     # ;
-	/// ```
-	pub fn env<K: Into<OsString>, V: Into<OsString>>(
+	/// If not set, the flag is omitted and `wesl` uses its default format version.
+	pub const fn format_version(
 		&mut self,
-		key: K,
-		val: V,
+		version: u32,
 	) -> &mut Self {
-		self.env.insert(key.into(), Some(val.into()));
+		self.format_version = Some(version);
 		self
 	}
 
-	/// Arbitrary environment variables to remove when running `cargo`. These will be merged into
-	/// the calling environment, overriding any which clash.
-	///
+	/// Set whether to require `wesl.lock` to be up to date with `--locked`.
+	pub const fn locked(
+		&mut self,
+		locked: bool,
+	) -> &mut Self {
+		self.locked = locked;
+		self
+	}
+
+	/// Set whether to run without accessing the network with `--offline`.
+	pub const fn offline(
+		&mut self,
+		offline: bool,
+	) -> &mut Self {
+		self.offline = offline;
+		self
+	}
+
+	/// Set whether to require `wesl.lock` to be up to date and run without accessing the
+	/// network with `--frozen`.
+	pub const fn frozen(
+		&mut self,
+		frozen: bool,
+	) -> &mut Self {
+		self.frozen = frozen;
+		self
+	}
+
+	/// Set whether `wesl` colors its output with `--color`.
+	///
+	/// If not set, the flag is omitted and `wesl` decides.
+	pub const fn color(
+		&mut self,
+		choice: ColorChoice,
+	) -> &mut Self {
+		self.color = Some(choice);
+		self
+	}
+
+	/// Select a package to report on. Can be called multiple times.
+	pub fn package<Specish: Into<String>>(
+		&mut self,
+		spec: Specish,
+	) -> &mut Self {
+		self.packages.push(spec.into());
+		self
+	}
+
+	/// Exclude a package from the report. Can be called multiple times.
+	pub fn exclude<Specish: Into<String>>(
+		&mut self,
+		spec: Specish,
+	) -> &mut Self {
+		self.excludes.push(spec.into());
+		self
+	}
+
+	/// Arbitrary command line flags to pass to `wesl`.
+	/// These will be added to the end of the command line invocation.
+	pub fn other_options<Options: Into<Vec<String>>>(
+		&mut self,
+		options: Options,
+	) -> &mut Self {
+		self.other_options = options.into();
+		self
+	}
+
+	/// Append a command line flag to pass to `wesl`, after those already added with
+	/// [`Self::other_options`], [`Self::arg`] or [`Self::args`].
+	pub fn arg<Arg: Into<String>>(
+		&mut self,
+		argument: Arg,
+	) -> &mut Self {
+		self.other_options.push(argument.into());
+		self
+	}
+
+	/// Append command line flags to pass to `wesl`, see [`Self::arg`].
+	pub fn args<Args, Arg>(
+		&mut self,
+		arguments: Args,
+	) -> &mut Self
+	where
+		Args: IntoIterator<Item = Arg>,
+		Arg: Into<String>,
+	{
+		self.other_options
+			.extend(arguments.into_iter().map(Into::into));
+		self
+	}
+
+	/// Arbitrary environment variables to set when running `wesl`.
+	/// These will be merged into the calling environment, overriding any which clash.
+	///
+	/// Some examples of when you may want to use this:
+	/// 1. Setting cargo config values without needing a .cargo/config.toml file, e.g. to set
+	///    `CARGO_NET_GIT_FETCH_WITH_CLI=true`
+	/// 2. To specify a custom path to RUSTC if your rust toolchain components aren't laid out in
+	///    the way cargo expects by default.
+	///
+	/// ```no_run
+	/// # use wesl_metadata::MetadataCommand;
+	/// MetadataCommand::new()
+	///     .env("OUT_DIR", "example/value")
+	///     // ...
+	///     # ;
+	/// ```
+	pub fn env<K: Into<OsString>, V: Into<OsString>>(
+		&mut self,
+		key: K,
+		val: V,
+	) -> &mut Self {
+		self.env.insert(key.into(), Some(val.into()));
+		self
+	}
+
+	/// Arbitrary environment variables to remove when running `cargo`. These will be merged into
+	/// the calling environment, overriding any which clash.
+	///
 	/// Some examples of when you may want to use this:
 	/// - Removing inherited environment variables in build scripts that can cause an error
 	///   when calling `wesl metadata` (for example, when cross-compiling).
@@ -622,7 +2122,91 @@ impl MetadataCommand {
 		self
 	}
 
-	/// Set whether to show stderr
+	/// Arbitrary environment variables to set when running `wesl`, see [`Self::env`].
+	pub fn envs<I, K, V>(
+		&mut self,
+		variables: I,
+	) -> &mut Self
+	where
+		I: IntoIterator<Item = (K, V)>,
+		K: Into<OsString>,
+		V: Into<OsString>,
+	{
+		for (key, val) in variables {
+			self.env(key, val);
+		}
+		self
+	}
+
+	/// Do not inherit the calling environment when running `wesl`.
+	///
+	/// Only the variables set with [`Self::env`] or [`Self::envs`] will be visible to `wesl`,
+	/// regardless of whether they were set before or after calling this method.
+	///
+	/// ```no_run
+	/// # use wesl_metadata::MetadataCommand;
+	/// MetadataCommand::new()
+	///     .env_clear()
+	///     .env("PATH", "/usr/bin")
+	///     // ...
+	///     # ;
+	/// ```
+	pub const fn env_clear(&mut self) -> &mut Self {
+		self.env_clear = true;
+		self
+	}
+
+	/// Remove every environment variable set with [`Self::env`] or removed with
+	/// [`Self::env_remove`].
+	///
+	/// Unlike [`Self::env_clear`], this does not stop the calling environment from being
+	/// inherited.
+	pub fn reset_env_overrides(&mut self) -> &mut Self {
+		self.env.clear();
+		self
+	}
+
+	/// Set whether `exec` checks that `manifest_path` is an existing file before running `wesl`.
+	///
+	/// This is enabled by default, returning [`Error::ManifestNotFound`] for a missing manifest.
+	/// Disable it to let `wesl` resolve the manifest path itself.
+	pub const fn check_manifest_path(
+		&mut self,
+		check: bool,
+	) -> &mut Self {
+		self.skip_manifest_check = !check;
+		self
+	}
+
+	/// Set whether to reject unknown top-level fields in the output of `wesl metadata`
+	/// with [`Error::UnknownField`], instead of ignoring them.
+	///
+	/// This helps to notice changes in the output of newer `wesl` versions.
+	/// It does not apply to [`Self::exec_visit`], which ignores other fields by design.
+	pub const fn strict(
+		&mut self,
+		strict: bool,
+	) -> &mut Self {
+		self.strict = strict;
+		self
+	}
+
+	/// Set whether [`Self::exec_partial`] parses the JSON that a failed `wesl metadata`
+	/// printed, e.g. for the packages it processed before a broken manifest.
+	///
+	/// This does not change [`Self::exec`], which fails whenever `wesl metadata` does.
+	pub const fn allow_partial(
+		&mut self,
+		allow: bool,
+	) -> &mut Self {
+		self.allow_partial = allow;
+		self
+	}
+
+	/// Set whether to show stderr.
+	///
+	/// Stderr is always captured. When verbose, it is also echoed to the stderr of this
+	/// process once `wesl metadata` exits.
 	pub const fn verbose(
 		&mut self,
 		verbose: bool,
@@ -631,31 +2215,124 @@ impl MetadataCommand {
 		self
 	}
 
-	/// Builds a command for `wesl metadata`. This is the first
-	/// part of the work of `exec`.
+	/// The path to the `wesl` executable, if set with [`Self::wesl_path`].
 	#[must_use]
-	pub fn wesl_command(&self) -> Command {
-		let wesl = self
-			.wesl_path
-			.clone()
-			.or_else(|| env::var("WESL").map(PathBuf::from).ok())
-			.unwrap_or_else(|| PathBuf::from("wesl"));
-		let mut cmd = Command::new(wesl);
-		cmd.arg("metadata");
+	pub fn get_wesl_path(&self) -> Option<&Path> {
+		self.wesl_path.as_deref()
+	}
 
-		if self.no_dependencies {
-			cmd.arg("--no-dependencies");
-		}
+	/// The path to `wesl.toml`, if set with [`Self::manifest_path`].
+	#[must_use]
+	pub fn get_manifest_path(&self) -> Option<&Path> {
+		self.manifest_path.as_deref()
+	}
+
+	/// The current directory of the `wesl metadata` process, if set with [`Self::current_dir`].
+	#[must_use]
+	pub fn get_current_dir(&self) -> Option<&Path> {
+		self.current_dir.as_deref()
+	}
+
+	/// Whether dependencies are left out, see [`Self::set_no_dependencies`].
+	#[must_use]
+	pub const fn is_no_dependencies(&self) -> bool {
+		self.no_dependencies
+	}
+
+	/// The requested metadata format version, if set with [`Self::format_version`].
+	#[must_use]
+	pub const fn get_format_version(&self) -> Option<u32> {
+		self.format_version
+	}
+
+	/// Whether `--locked` is passed, see [`Self::locked`].
+	#[must_use]
+	pub const fn is_locked(&self) -> bool {
+		self.locked
+	}
+
+	/// Whether `--offline` is passed, see [`Self::offline`].
+	#[must_use]
+	pub const fn is_offline(&self) -> bool {
+		self.offline
+	}
+
+	/// Whether `--frozen` is passed, see [`Self::frozen`].
+	#[must_use]
+	pub const fn is_frozen(&self) -> bool {
+		self.frozen
+	}
+
+	/// The color choice, if set with [`Self::color`].
+	#[must_use]
+	pub const fn get_color(&self) -> Option<ColorChoice> {
+		self.color
+	}
+
+	/// The package specs selected with [`Self::package`].
+	#[must_use]
+	pub fn get_packages(&self) -> &[String] {
+		&self.packages
+	}
+
+	/// The package specs excluded with [`Self::exclude`].
+	#[must_use]
+	pub fn get_excludes(&self) -> &[String] {
+		&self.excludes
+	}
+
+	/// The flags set with [`Self::other_options`].
+	#[must_use]
+	pub fn get_other_options(&self) -> &[String] {
+		&self.other_options
+	}
+
+	/// The environment variables to set, or to remove when `None`.
+	pub fn get_envs(&self) -> impl Iterator<Item = (&OsStr, Option<&OsStr>)> {
+		self.env
+			.iter()
+			.map(|(key, value)| (key.as_os_str(), value.as_deref()))
+	}
+
+	/// Whether the calling environment is not inherited, see [`Self::env_clear`].
+	#[must_use]
+	pub const fn is_env_clear(&self) -> bool {
+		self.env_clear
+	}
+
+	/// Whether unknown fields are rejected, see [`Self::strict`].
+	#[must_use]
+	pub const fn is_strict(&self) -> bool {
+		self.strict
+	}
+
+	/// Whether the output of a failed run is parsed, see [`Self::allow_partial`].
+	#[must_use]
+	pub const fn is_partial_allowed(&self) -> bool {
+		self.allow_partial
+	}
+
+	/// Whether stderr is echoed, see [`Self::verbose`].
+	#[must_use]
+	pub const fn is_verbose(&self) -> bool {
+		self.verbose
+	}
+
+	/// Builds a command for the `wesl` executable at `wesl`, in the configured directory and
+	/// environment but without arguments.
+	fn base_command(
+		&self,
+		wesl: PathBuf,
+	) -> Command {
+		let mut cmd = Command::new(wesl);
 
 		if let Some(path) = self.current_dir.as_ref() {
 			cmd.current_dir(path);
 		}
 
-		if let Some(manifest_path) = &self.manifest_path {
-			cmd.arg("--manifest-path").arg(manifest_path.as_os_str());
+		if self.env_clear {
+			cmd.env_clear();
 		}
-		cmd.args(&self.other_options);
-
 		for (key, val) in &self.env {
 			match val {
 				Some(val) => cmd.env(key, val),
@@ -666,37 +2343,2015 @@ impl MetadataCommand {
 		cmd
 	}
 
+	/// Runs `command`, built by [`Self::base_command`], and captures its output.
+	fn output(
+		&self,
+		command: &mut Command,
+	) -> Result<Output> {
+		command.output().map_err(|error| {
+			let missing_current_dir = self
+				.current_dir
+				.as_ref()
+				.is_some_and(|current_dir| !current_dir.is_dir());
+			if error.kind() == io::ErrorKind::NotFound && !missing_current_dir {
+				Error::WeslNotFound {
+					attempted: command.get_program().into(),
+					candidates: vec![command.get_program().into()],
+				}
+			} else {
+				Error::Io(error)
+			}
+		})
+	}
+
+	/// Runs `wesl --version` with the configured executable, directory and environment, and
+	/// returns the last version in its output, e.g. `0.2.1` for `wesl 0.2.1 (4f2c1e0)`.
+	///
+	/// A leading `v` of the version is ignored, and so are bytes of the output that are not
+	/// valid UTF-8.
+	pub fn wesl_version(&self) -> Result<Version> {
+		let mut command = self.base_command(self.resolve_wesl()?);
+		command.arg("--version");
+		let output = self.output(&mut command)?;
+		if !output.status.success() {
+			return Err(Error::WeslVersion {
+				output: String::from_utf8(output.stderr)?,
+			});
+		}
+		let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+		stdout
+			.split_whitespace()
+			.rev()
+			.find_map(|word| {
+				word.trim_start_matches('v')
+					.trim_end_matches([',', ';', ')'])
+					.parse()
+					.ok()
+			})
+			.ok_or(Error::WeslVersion { output: stdout })
+	}
+
+	/// `path` joined to the current directory if one is set.
+	fn in_current_dir(
+		&self,
+		path: &Path,
+	) -> PathBuf {
+		match &self.current_dir {
+			Some(current_dir) => current_dir.join(path),
+			None => path.to_owned(),
+		}
+	}
+
+	/// The manifest path passed to `wesl`, with `wesl.toml` appended if it is a directory.
+	fn effective_manifest_path(&self) -> Option<PathBuf> {
+		let manifest_path = self.manifest_path.as_ref()?;
+		Some(if self.in_current_dir(manifest_path).is_dir() {
+			manifest_path.join("wesl.toml")
+		} else {
+			manifest_path.clone()
+		})
+	}
+
+	/// Builds a command for `wesl metadata`. This is the first
+	/// part of the work of `exec`.
+	///
+	/// The configured executable is used as it is, without searching `PATH` like `exec`.
+	#[must_use]
+	pub fn wesl_command(&self) -> Command {
+		self.metadata_command(self.wesl_program())
+	}
+
+	/// Builds a command for `wesl metadata` with the `wesl` executable at `wesl`.
+	fn metadata_command(
+		&self,
+		wesl: PathBuf,
+	) -> Command {
+		let mut cmd = self.base_command(wesl);
+		cmd.arg("metadata");
+
+		if self.no_dependencies {
+			cmd.arg("--no-dependencies");
+		}
+		if let Some(version) = self.format_version {
+			cmd.arg("--format-version").arg(version.to_string());
+		}
+		for flag in self.typed_flags() {
+			cmd.arg(flag);
+		}
+		if let Some(choice) = self.color {
+			cmd.arg("--color").arg(choice.as_str());
+		}
+
+		if let Some(manifest_path) = self.effective_manifest_path() {
+			cmd.arg("--manifest-path").arg(manifest_path);
+		}
+		for spec in &self.packages {
+			cmd.arg("--package").arg(spec);
+		}
+		for spec in &self.excludes {
+			cmd.arg("--exclude").arg(spec);
+		}
+		cmd.args(&self.other_options);
+
+		cmd
+	}
+
 	/// Parses `wesl metadata` output. `data` must have been
 	/// produced by a command built with `wesl_command`.
 	pub fn parse<T: AsRef<str>>(data: T) -> Result<Metadata> {
-		let meta = serde_json::from_str(data.as_ref())?;
+		let mut meta: Metadata = serde_json::from_str(data.as_ref())?;
+		meta.reindex();
 		Ok(meta)
 	}
 
 	/// Runs configured `wesl metadata` and returns parsed `Metadata`.
 	pub fn exec(&self) -> Result<Metadata> {
-		let mut command = self.wesl_command();
+		self.parse_output(from_utf8(&self.run()?.stdout)?)
+	}
+
+	/// Runs `wesl metadata` for the manifest at `path` and returns parsed `Metadata`.
+	///
+	/// This is a shorthand for `MetadataCommand::new().manifest_path(path).exec()`.
+	pub fn exec_for_manifest<Pathish: Into<PathBuf>>(path: Pathish) -> Result<Metadata> {
+		Self::new().manifest_path(path).exec()
+	}
+
+	/// Runs `wesl metadata` in the current directory and returns parsed `Metadata`.
+	///
+	/// This is a shorthand for `MetadataCommand::new().exec()`.
+	pub fn exec_current_dir() -> Result<Metadata> {
+		Self::new().exec()
+	}
+
+	/// Runs configured `wesl metadata` in `directory` and returns parsed `Metadata`.
+	///
+	/// This is [`Self::exec`] on a copy of this command with [`Self::current_dir`] set.
+	pub fn exec_in<Pathish: Into<PathBuf>>(
+		&self,
+		directory: Pathish,
+	) -> Result<Metadata> {
+		self.clone().current_dir(directory).exec()
+	}
+
+	/// Runs configured `wesl metadata` and returns its captured output.
+	///
+	/// With `verbose`, the captured stderr is echoed to the stderr of this process.
+	fn run(&self) -> Result<Output> {
+		if !self.skip_manifest_check
+			&& let Some(manifest_path) = self.effective_manifest_path()
+		{
+			let path = self.in_current_dir(&manifest_path);
+			if !path.is_file() {
+				return Err(Error::ManifestNotFound { path });
+			}
+		}
+
+		let wesl = self.resolve_wesl()?;
+		let output = self.output(&mut self.metadata_command(wesl))?;
 		if self.verbose {
-			command.stderr(Stdio::inherit());
+			io::stderr().write_all(&output.stderr)?;
 		}
-		let output = command.output()?;
 		if !output.status.success() {
+			let exit_code = output.status.code();
+			let stderr = String::from_utf8(output.stderr)?;
+			let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+			if let Some(flag) = self.unsupported_flag(&stderr) {
+				return Err(Error::UnsupportedFlag {
+					flag: flag.to_owned(),
+					exit_code,
+					stderr,
+					stdout,
+				});
+			}
 			return Err(Error::WeslMetadata {
-				stderr: String::from_utf8(output.stderr)?,
+				exit_code,
+				stderr,
+				stdout,
 			});
 		}
-		let stdout = from_utf8(&output.stdout)?
-			.lines()
-			.find(|line| line.starts_with('{'))
-			.ok_or(Error::NoJson)?;
-		Self::parse(stdout)
+		Ok(output)
 	}
-}
 
-#[cfg(test)]
-mod tests {
-	use super::*;
+	/// The enabled flags of the boolean setters that only some versions of `wesl` support.
+	fn typed_flags(&self) -> impl Iterator<Item = &'static str> {
+		[
+			(self.locked, "--locked"),
+			(self.offline, "--offline"),
+			(self.frozen, "--frozen"),
+		]
+		.into_iter()
+		.filter_map(|(enabled, flag)| enabled.then_some(flag))
+	}
 
-	#[test]
-	fn todo() {}
+	/// The flag added by a typed setter that `stderr` reports as not recognized, if any.
+	fn unsupported_flag(
+		&self,
+		stderr: &str,
+	) -> Option<&'static str> {
+		let format_version = self.format_version.map(|_| "--format-version");
+		let mut flags = self.typed_flags().chain(format_version);
+		flags.find(|flag| {
+			stderr.lines().any(|line| {
+				let line = line.to_ascii_lowercase();
+				["unexpected argument", "unrecognized", "unknown"]
+					.iter()
+					.any(|complaint| line.contains(complaint))
+					&& line
+						.split(|character: char| {
+							!(character.is_ascii_alphanumeric() || character == '-')
+						})
+						.any(|word| word == *flag)
+			})
+		})
+	}
+
+	/// Parses the JSON in the stdout of `wesl metadata`, skipping any lines before it.
+	///
+	/// The JSON starts at the first line beginning with `{` and may span multiple lines.
+	/// Anything after the JSON value is ignored.
+	fn parse_stdout(stdout: &str) -> Result<Metadata> {
+		let mut deserializer = serde_json::Deserializer::from_str(json_start(stdout)?);
+		let mut meta = Metadata::deserialize(&mut deserializer)?;
+		meta.reindex();
+		Ok(meta)
+	}
+
+	/// Parses the stdout of `wesl metadata` like [`Self::parse_stdout`], rejecting unknown
+	/// top-level fields.
+	fn parse_stdout_strict(stdout: &str) -> Result<Metadata> {
+		let mut deserializer = serde_json::Deserializer::from_str(json_start(stdout)?);
+		let value = serde_json::Value::deserialize(&mut deserializer)?;
+		if let Some(field) = value
+			.as_object()
+			.into_iter()
+			.flat_map(serde_json::Map::keys)
+			.find(|field| !METADATA_FIELDS.contains(&field.as_str()))
+		{
+			return Err(Error::UnknownField {
+				field: field.clone(),
+			});
+		}
+		let mut meta = Metadata::deserialize(value)?;
+		meta.reindex();
+		Ok(meta)
+	}
+
+	/// Parses the stdout of `wesl metadata`, strictly if this command is [`Self::strict`].
+	fn parse_output(
+		&self,
+		stdout: &str,
+	) -> Result<Metadata> {
+		if self.strict {
+			Self::parse_stdout_strict(stdout)
+		} else {
+			Self::parse_stdout(stdout)
+		}
+	}
+}
+
+/// Skips the lines of `stdout` before the first one starting with `{`.
+///
+/// Output of nothing but whitespace is [`Error::EmptyOutput`] rather than [`Error::NoJson`].
+fn json_start(stdout: &str) -> Result<&str> {
+	if stdout.trim().is_empty() {
+		return Err(Error::EmptyOutput);
+	}
+	let start = if stdout.starts_with('{') {
+		0
+	} else {
+		stdout.find("\n{").ok_or(Error::NoJson)? + 1
+	};
+	stdout.get(start..).ok_or(Error::NoJson)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn todo() {}
+
+	pub(crate) fn package(
+		name: &str,
+		version: &str,
+	) -> Package {
+		Package {
+			name: name.to_owned(),
+			version: version.parse().unwrap(),
+			authors: Vec::new(),
+			id: PackageId {
+				repr: format!("{name}@{version}"),
+			},
+			source: None,
+			description: None,
+			dependencies: Vec::new(),
+			targets: Vec::new(),
+			features: BTreeMap::new(),
+			license: None,
+			license_file: None,
+			manifest_path: Utf8PathBuf::from(format!("/{name}/wesl.toml")),
+			categories: Vec::new(),
+			keywords: Vec::new(),
+			readme: None,
+			repository: None,
+			homepage: None,
+			documentation: None,
+			edition: Edition::default(),
+			default_entry_point: None,
+			include: Vec::new(),
+			exclude: Vec::new(),
+			metadata: serde_json::Value::Null,
+		}
+	}
+
+	pub(crate) fn metadata(packages: Vec<Package>) -> Metadata {
+		Metadata {
+			package_manager: PackageManager::Cargo,
+			packages,
+			workspace_members: Vec::new(),
+			resolve: None,
+			target_directory: Utf8PathBuf::from("/target"),
+			version: 1,
+			root_package_directory: Utf8PathBuf::from("/"),
+			package_index: PackageIndex::default(),
+		}
+	}
+
+	#[test]
+	fn index_by_id() {
+		let metadata = metadata(
+			(0..500)
+				.map(|index| package(&format!("package-{index}"), "1.0.0"))
+				.collect(),
+		);
+		let index = metadata.index_by_id();
+		assert_eq!(index.len(), 500);
+		for package in &metadata.packages {
+			assert_eq!(index.get(&package.id), Some(&package));
+			assert_eq!(&metadata[&package.id], package);
+		}
+	}
+
+	#[test]
+	fn resolve_map() {
+		let mut metadata = metadata(vec![package("app", "1.0.0"), package("lib", "1.0.0")]);
+		assert!(metadata.resolve_map().is_empty());
+
+		metadata.resolve = Some(Resolve {
+			nodes: vec![node("app@1.0.0", &["lib@1.0.0"]), node("lib@1.0.0", &[])],
+			root: None,
+		});
+		let nodes = metadata.resolve_map();
+		assert_eq!(nodes.len(), 2);
+		assert_eq!(
+			nodes[&PackageId::from("app@1.0.0")].dependencies,
+			[PackageId::from("lib@1.0.0")]
+		);
+		assert_eq!(nodes.get(&PackageId::from("missing")), None);
+	}
+
+	pub(crate) fn node(
+		id: &str,
+		dependencies: &[&str],
+	) -> Node {
+		Node {
+			id: PackageId {
+				repr: id.to_owned(),
+			},
+			renamed_dependencies: Vec::new(),
+			dependencies: dependencies
+				.iter()
+				.map(|dependency| PackageId {
+					repr: (*dependency).to_owned(),
+				})
+				.collect(),
+		}
+	}
+
+	#[test]
+	fn resolve_stats() {
+		let mut app = node("app", &["lib", "util"]);
+		app.renamed_dependencies.push(NodeDependency {
+			name: "helpers".to_owned(),
+			pkg: PackageId::from("util"),
+		});
+		let mut resolve = Resolve {
+			nodes: vec![
+				app,
+				node("lib", &["util", "app"]),
+				node("util", &["deep"]),
+				node("deep", &[]),
+				node("tool", &["deep"]),
+			],
+			root: None,
+		};
+		assert_eq!(
+			resolve.stats(),
+			GraphStats {
+				nodes: 5,
+				edges: 6,
+				max_depth: None,
+				leaves: 1,
+			}
+		);
+		resolve.root = Some(PackageId::from("app"));
+		assert_eq!(resolve.stats().max_depth, Some(2));
+	}
+
+	#[test]
+	fn resolve_roots() {
+		let resolve = Resolve {
+			nodes: vec![
+				node("util", &[]),
+				node("app", &["lib", "util"]),
+				node("lib", &["util"]),
+				node("tool", &["util"]),
+			],
+			root: Some(PackageId::from("app")),
+		};
+		assert_eq!(
+			resolve.roots(),
+			[&PackageId::from("app"), &PackageId::from("tool")]
+		);
+		let empty = Resolve {
+			nodes: Vec::new(),
+			root: None,
+		};
+		assert!(empty.roots().is_empty());
+	}
+
+	#[test]
+	fn resolve_leaves() {
+		let mut resolve = Resolve {
+			nodes: vec![
+				node("app", &["lib", "util"]),
+				node("tool", &["util"]),
+				node("lib", &["util"]),
+				node("util", &[]),
+			],
+			root: None,
+		};
+		let leaves: Vec<&str> = resolve.leaves().map(|node| node.id.repr.as_str()).collect();
+		assert_eq!(leaves, ["util"]);
+
+		assert_eq!(
+			resolve
+				.get(&PackageId::from("lib"))
+				.map(|node| node.dependencies.len()),
+			Some(1)
+		);
+		assert_eq!(resolve.get(&PackageId::from("missing")), None);
+
+		assert_eq!(resolve.root_node(), None);
+		resolve.root = Some(PackageId::from("app"));
+		assert_eq!(
+			resolve.root_node().map(|node| node.id.as_str()),
+			Some("app")
+		);
+		let root: Vec<&str> = resolve
+			.iter()
+			.filter(|node| node.is_root(&resolve))
+			.map(|node| node.id.as_str())
+			.collect();
+		assert_eq!(root, ["app"]);
+	}
+
+	#[test]
+	fn legacy_node_dependencies() {
+		let modern = r#"{"id": "app", "dependencies": ["util"], "renamed_dependencies": [{"name": "helpers", "pkg": "util"}]}"#;
+		let legacy = r#"{"id": "app", "dependencies": ["util"], "deps": [{"name": "helpers", "pkg": "util", "dep_kinds": [{"kind": null, "target": null}]}]}"#;
+		let both = r#"{"id": "app", "dependencies": ["util"], "deps": [{"name": "stale", "pkg": "util"}], "renamed_dependencies": [{"name": "helpers", "pkg": "util"}]}"#;
+
+		let expected: Node = serde_json::from_str(modern).unwrap();
+		assert_eq!(
+			expected
+				.renamed_dep_by_name("helpers")
+				.map(|dependency| dependency.pkg.as_str()),
+			Some("util")
+		);
+		for json in [legacy, both] {
+			assert_eq!(serde_json::from_str::<Node>(json).unwrap(), expected);
+		}
+
+		let written = serde_json::to_value(&expected).unwrap();
+		assert!(written.get("renamed_dependencies").is_some());
+		assert!(written.get("deps").is_none());
+		assert_eq!(serde_json::from_value::<Node>(written).unwrap(), expected);
+	}
+
+	#[test]
+	fn node_dependency_lookup() {
+		let mut app = node("app", &[]);
+		app.renamed_dependencies.push(NodeDependency {
+			name: "helpers".to_owned(),
+			pkg: PackageId::from("util"),
+		});
+		assert_eq!(
+			app.renamed_dep_by_name("helpers")
+				.map(|dependency| dependency.pkg.as_str()),
+			Some("util")
+		);
+		assert_eq!(app.renamed_dep_by_name("util"), None);
+		assert_eq!(
+			app.dep_by_pkg_id(&PackageId::from("util"))
+				.map(|dependency| dependency.name.as_str()),
+			Some("helpers")
+		);
+		assert_eq!(app.dep_by_pkg_id(&PackageId::from("helpers")), None);
+	}
+
+	#[test]
+	fn node_depends_on() {
+		let mut app = node("app", &["lib"]);
+		app.renamed_dependencies.push(NodeDependency {
+			name: "helpers".to_owned(),
+			pkg: PackageId::from("util"),
+		});
+		assert!(app.depends_on(&PackageId::from("lib")));
+		assert!(app.depends_on(&PackageId::from("util")));
+		assert!(!app.dependencies.contains(&PackageId::from("util")));
+		assert!(!app.depends_on(&PackageId::from("helpers")));
+		assert!(!app.depends_on(&PackageId::from("app")));
+	}
+
+	fn command_args(command: &MetadataCommand) -> Vec<String> {
+		command
+			.wesl_command()
+			.get_args()
+			.map(|argument| argument.to_str().unwrap().to_owned())
+			.collect()
+	}
+
+	#[test]
+	fn append_args() {
+		let mut command = MetadataCommand::new();
+		command
+			.arg("--first")
+			.args(["--second", "value"])
+			.arg(String::from("--third"));
+		assert_eq!(
+			command_args(&command),
+			["metadata", "--first", "--second", "value", "--third"]
+		);
+		command
+			.other_options(vec!["--replaced".to_owned()])
+			.arg("--last");
+		assert_eq!(command.get_other_options(), ["--replaced", "--last"]);
+	}
+
+	#[test]
+	fn manifest_path_from_env() {
+		let mut command = MetadataCommand::new();
+		command.manifest_path_from_variable(None);
+		assert_eq!(command.get_manifest_path(), None);
+
+		command.manifest_path_from_variable(Some("/ci/app/wesl.toml".into()));
+		assert_eq!(
+			command_args(&command),
+			["metadata", "--manifest-path", "/ci/app/wesl.toml"]
+		);
+	}
+
+	#[test]
+	fn env_overrides() {
+		let mut command = MetadataCommand::new();
+		command
+			.env("WESL_KEPT", "1")
+			.env_remove("WESL_REMOVED")
+			.env_clear();
+		assert_eq!(
+			command.get_envs().collect::<Vec<_>>(),
+			[
+				(OsStr::new("WESL_KEPT"), Some(OsStr::new("1"))),
+				(OsStr::new("WESL_REMOVED"), None),
+			]
+		);
+		command.reset_env_overrides();
+		assert_eq!(command.get_envs().count(), 0);
+		assert!(command.is_env_clear());
+	}
+
+	#[test]
+	fn package_selection_args() {
+		let mut command = MetadataCommand::new();
+		command
+			.package("app")
+			.exclude("tool")
+			.package("lib")
+			.other_options(vec!["--extra".to_owned()]);
+		assert_eq!(
+			command_args(&command),
+			[
+				"metadata",
+				"--package",
+				"app",
+				"--package",
+				"lib",
+				"--exclude",
+				"tool",
+				"--extra"
+			]
+		);
+	}
+
+	#[test]
+	fn no_dependencies() {
+		let mut command = MetadataCommand::new();
+		command.manifest_path("wesl.toml").set_no_dependencies(true);
+		assert!(command.is_no_dependencies());
+		assert!(command_args(&command).contains(&"--no-dependencies".to_owned()));
+
+		let with_dependencies = command.clone_with_dependencies();
+		assert!(!with_dependencies.is_no_dependencies());
+		assert!(!command_args(&with_dependencies).contains(&"--no-dependencies".to_owned()));
+		assert_eq!(
+			with_dependencies.get_manifest_path(),
+			Some(Path::new("wesl.toml"))
+		);
+
+		command.set_no_dependencies(false);
+		assert!(!command_args(&command).contains(&"--no-dependencies".to_owned()));
+
+		#[expect(deprecated, reason = "the zero-argument form must keep working")]
+		command.no_dependencies();
+		assert!(command_args(&command).contains(&"--no-dependencies".to_owned()));
+	}
+
+	#[test]
+	fn format_version() {
+		let mut command = MetadataCommand::new();
+		assert_eq!(command_args(&command), ["metadata"]);
+		command.format_version(2);
+		assert_eq!(command.get_format_version(), Some(2));
+		assert_eq!(
+			command_args(&command),
+			["metadata", "--format-version", "2"]
+		);
+	}
+
+	#[test]
+	fn color() {
+		let mut command = MetadataCommand::new();
+		assert_eq!(command_args(&command), ["metadata"]);
+		for (choice, value) in [
+			(ColorChoice::Auto, "auto"),
+			(ColorChoice::Always, "always"),
+			(ColorChoice::Never, "never"),
+		] {
+			command.color(choice);
+			assert_eq!(command.get_color(), Some(choice));
+			assert_eq!(command_args(&command), ["metadata", "--color", value]);
+		}
+	}
+
+	#[test]
+	fn typed_flags() {
+		let mut command = MetadataCommand::new();
+		command.locked(true).offline(true).frozen(true);
+		assert_eq!(
+			command_args(&command),
+			["metadata", "--locked", "--offline", "--frozen"]
+		);
+		command.locked(false).frozen(false);
+		assert!(command.is_offline() && !command.is_locked() && !command.is_frozen());
+		assert_eq!(command_args(&command), ["metadata", "--offline"]);
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn exec_in() {
+		let directory = TempDir::new("exec-in");
+		let wesl = directory.shim(
+			"exec-in",
+			r#"printf '{"package_manager": "Cargo", "packages": [], "target_directory": "%s/target", "version": 1, "root_package_directory": "%s"}' "$PWD" "$PWD""#,
+		);
+		let workspace = wesl.with_file_name("exec-in-workspace");
+		std::fs::create_dir_all(&workspace).unwrap();
+		let workspace = workspace.canonicalize().unwrap();
+
+		let mut command = MetadataCommand::new();
+		command.wesl_path(wesl);
+		let metadata = command.exec_in(&workspace).unwrap();
+		assert_eq!(metadata.root_package_directory, workspace.to_str().unwrap());
+		assert_eq!(command.get_current_dir(), None);
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn unsupported_flag() {
+		let directory = TempDir::new("unsupported-flag");
+		let wesl = directory.shim(
+			"unsupported-flag",
+			"echo 'Usage: wesl metadata'\necho \"error: unexpected argument '--frozen' found\" >&2\nexit 2",
+		);
+		let mut command = MetadataCommand::new();
+		command.wesl_path(wesl).frozen(true);
+		let error = command.exec().unwrap_err();
+		assert!(
+			matches!(
+				&error,
+				Error::UnsupportedFlag { flag, exit_code: Some(2), stdout, .. }
+					if flag == "--frozen" && stdout == "Usage: wesl metadata\n"
+			),
+			"{error:?}"
+		);
+
+		// without the flag, the same failure is reported as is
+		command.frozen(false);
+		assert!(matches!(
+			command.exec().unwrap_err(),
+			Error::WeslMetadata {
+				exit_code: Some(2),
+				..
+			}
+		));
+	}
+
+	#[test]
+	fn retain_packages() {
+		let mut metadata = metadata(vec![
+			package("app", "1.0.0"),
+			package("lib", "1.0.0"),
+			package("util", "1.0.0"),
+		]);
+		let mut app = node("app@1.0.0", &["lib@1.0.0"]);
+		app.renamed_dependencies.push(NodeDependency {
+			name: "helpers".to_owned(),
+			pkg: PackageId {
+				repr: "util@1.0.0".to_owned(),
+			},
+		});
+		metadata.resolve = Some(Resolve {
+			nodes: vec![
+				app,
+				node("lib@1.0.0", &["util@1.0.0"]),
+				node("util@1.0.0", &[]),
+			],
+			root: Some(PackageId {
+				repr: "app@1.0.0".to_owned(),
+			}),
+		});
+
+		metadata.retain_packages(|package| package.name != "util");
+		let resolve = metadata.resolve.as_ref().unwrap();
+		assert_eq!(metadata.packages.len(), 2);
+		assert_eq!(resolve.nodes.len(), 2);
+		assert!(resolve.nodes.iter().all(|node| {
+			node.dependency_ids()
+				.all(|dependency| dependency.repr != "util@1.0.0")
+		}));
+		assert!(resolve.root.is_some());
+
+		metadata.retain_packages(|package| package.name != "app");
+		let resolve = metadata.resolve.as_ref().unwrap();
+		assert_eq!(resolve.nodes.len(), 1);
+		assert_eq!(resolve.root, None);
+		assert!(metadata.root_package().is_none());
+	}
+
+	#[test]
+	fn dependency_closure() {
+		let resolve = Resolve {
+			nodes: vec![
+				node("app", &["lib"]),
+				node("lib", &["util", "missing"]),
+				node("util", &["lib"]),
+				node("tool", &["deep"]),
+				node("deep", &[]),
+				node("other", &[]),
+			],
+			root: None,
+		};
+		let closure = |roots: &[&str]| -> Vec<String> {
+			let roots: Vec<PackageId> = roots.iter().map(|root| PackageId::from(*root)).collect();
+			resolve
+				.dependency_closure(&roots)
+				.into_iter()
+				.map(|id| id.repr)
+				.collect()
+		};
+		assert_eq!(closure(&["app"]), ["app", "lib", "util"]);
+		assert_eq!(
+			closure(&["app", "tool", "unknown"]),
+			["app", "deep", "lib", "tool", "util"]
+		);
+		assert_eq!(closure(&["util"]), ["lib", "util"]);
+		assert!(closure(&[]).is_empty());
+	}
+
+	#[test]
+	fn resolve_subgraph() {
+		let mut lib = node("lib", &["util", "missing"]);
+		lib.renamed_dependencies.push(NodeDependency {
+			name: "helpers".to_owned(),
+			pkg: PackageId {
+				repr: "util".to_owned(),
+			},
+		});
+		let resolve = Resolve {
+			nodes: vec![
+				node("app", &["lib"]),
+				lib,
+				node("tool", &["util"]),
+				node("util", &[]),
+			],
+			root: Some(PackageId {
+				repr: "app".to_owned(),
+			}),
+		};
+
+		let subgraph = resolve.subgraph(&[PackageId {
+			repr: "lib".to_owned(),
+		}]);
+		let ids: Vec<&str> = subgraph
+			.nodes
+			.iter()
+			.map(|node| node.id.repr.as_str())
+			.collect();
+		assert_eq!(ids, ["lib", "util"]);
+		assert_eq!(subgraph.root, None);
+		let lib = subgraph.nodes.first().unwrap();
+		assert_eq!(
+			lib.dependencies,
+			[PackageId {
+				repr: "util".to_owned()
+			}]
+		);
+		assert_eq!(lib.renamed_dependencies.len(), 1);
+		assert_eq!(lib.renamed_dependencies.first().unwrap().name, "helpers");
+
+		let whole = resolve.subgraph(&[PackageId {
+			repr: "app".to_owned(),
+		}]);
+		assert_eq!(whole.nodes.len(), 3);
+		assert_eq!(whole.root, resolve.root);
+	}
+
+	fn ids(metadata: &Metadata) -> (Vec<&str>, Vec<&str>) {
+		(
+			metadata.iter().map(|package| package.id.as_str()).collect(),
+			metadata
+				.resolve
+				.iter()
+				.flatten()
+				.map(|node| node.id.as_str())
+				.collect(),
+		)
+	}
+
+	#[test]
+	fn shrink_to_reachable() {
+		let mut metadata = metadata(vec![
+			package("app", "1.0.0"),
+			package("lib", "1.0.0"),
+			package("tool", "1.0.0"),
+			package("stale", "1.0.0"),
+		]);
+		metadata.workspace_members =
+			vec![PackageId::from("app@1.0.0"), PackageId::from("tool@1.0.0")];
+		metadata.resolve = Some(Resolve {
+			nodes: vec![
+				node("app@1.0.0", &["lib@1.0.0"]),
+				node("lib@1.0.0", &[]),
+				node("tool@1.0.0", &["lib@1.0.0"]),
+				node("stale@1.0.0", &["lib@1.0.0"]),
+			],
+			root: None,
+		});
+
+		let mut workspace = metadata.clone();
+		workspace.shrink_to_reachable();
+		assert_eq!(
+			ids(&workspace),
+			(
+				vec!["app@1.0.0", "lib@1.0.0", "tool@1.0.0"],
+				vec!["app@1.0.0", "lib@1.0.0", "tool@1.0.0"]
+			)
+		);
+		assert!(workspace.get(&PackageId::from("stale@1.0.0")).is_none());
+
+		metadata.resolve.as_mut().unwrap().root = Some(PackageId::from("app@1.0.0"));
+		metadata.shrink_to_reachable();
+		assert_eq!(
+			ids(&metadata),
+			(
+				vec!["app@1.0.0", "lib@1.0.0"],
+				vec!["app@1.0.0", "lib@1.0.0"]
+			)
+		);
+		assert_eq!(metadata.workspace_members, [PackageId::from("app@1.0.0")]);
+	}
+
+	#[test]
+	fn subset() {
+		let mut packages = vec![
+			package("app", "1.0.0"),
+			package("left", "1.0.0"),
+			package("right", "1.0.0"),
+			package("lib", "1.0.0"),
+			package("lib", "2.0.0"),
+			package("ping", "1.0.0"),
+			package("pong", "1.0.0"),
+		];
+		packages[2].manifest_path = "/work/right/wesl.toml".into();
+		let mut metadata = metadata(packages);
+		metadata.workspace_members = vec![PackageId::from("app@1.0.0")];
+		metadata.resolve = Some(Resolve {
+			nodes: vec![
+				node("app@1.0.0", &["left@1.0.0", "right@1.0.0", "lib@2.0.0"]),
+				node("left@1.0.0", &["lib@1.0.0"]),
+				node("right@1.0.0", &["lib@1.0.0"]),
+				node("lib@1.0.0", &[]),
+				node("lib@2.0.0", &[]),
+				node("ping@1.0.0", &["pong@1.0.0"]),
+				node("pong@1.0.0", &["ping@1.0.0"]),
+			],
+			root: Some(PackageId::from("app@1.0.0")),
+		});
+
+		// a diamond below `app`, with an unreachable duplicate of `lib`
+		let right = metadata.subset(&[PackageId::from("right@1.0.0")]);
+		assert_eq!(
+			ids(&right),
+			(
+				vec!["right@1.0.0", "lib@1.0.0"],
+				vec!["right@1.0.0", "lib@1.0.0"]
+			)
+		);
+		let resolve = right.resolve.as_ref().unwrap();
+		assert_eq!(resolve.root, Some(PackageId::from("right@1.0.0")));
+		assert_eq!(right.root_package_directory, "/work/right");
+		assert!(right.workspace_members.is_empty());
+		assert!(right.get(&PackageId::from("lib@1.0.0")).is_some());
+
+		let whole = metadata.subset(&[PackageId::from("app@1.0.0")]);
+		assert_eq!(whole.packages.len(), 5);
+		assert_eq!(whole.workspace_members, metadata.workspace_members);
+
+		// a cycle
+		let cycle = metadata.subset(&[PackageId::from("pong@1.0.0")]);
+		assert_eq!(ids(&cycle).0, ["ping@1.0.0", "pong@1.0.0"]);
+		assert_eq!(
+			cycle.resolve.as_ref().unwrap().nodes,
+			[
+				node("ping@1.0.0", &["pong@1.0.0"]),
+				node("pong@1.0.0", &["ping@1.0.0"])
+			]
+		);
+
+		// several roots
+		let several =
+			metadata.subset(&[PackageId::from("ping@1.0.0"), PackageId::from("left@1.0.0")]);
+		assert_eq!(
+			ids(&several).0,
+			["left@1.0.0", "lib@1.0.0", "ping@1.0.0", "pong@1.0.0"]
+		);
+		assert_eq!(several.resolve.as_ref().unwrap().root, None);
+		assert_eq!(
+			several.root_package_directory,
+			metadata.root_package_directory
+		);
+
+		// without `resolve`, dependencies are followed by name
+		metadata.resolve = None;
+		for (package, dependencies) in metadata.packages.iter_mut().zip([
+			&["left", "lib"][..],
+			&["lib"],
+			&[],
+			&[],
+			&[],
+			&[],
+			&[],
+		]) {
+			package.dependencies = dependencies
+				.iter()
+				.map(|name| {
+					serde_json::from_value(serde_json::json!({
+						"name": name,
+						"rename": null,
+						"path": null,
+					}))
+					.unwrap()
+				})
+				.collect();
+		}
+		assert_eq!(
+			ids(&metadata.subset(&[PackageId::from("app@1.0.0")])),
+			(
+				vec!["app@1.0.0", "left@1.0.0", "lib@1.0.0", "lib@2.0.0"],
+				Vec::new()
+			)
+		);
+	}
+
+	/// A fixture directory under the system temporary directory, removed when dropped.
+	pub(crate) struct TempDir(Utf8PathBuf);
+
+	impl TempDir {
+		/// Create an empty `wesl-metadata-<pid>-<name>` directory, replacing any left over from an
+		/// earlier run.
+		pub(crate) fn new(name: &str) -> Self {
+			let path = Utf8PathBuf::try_from(env::temp_dir())
+				.unwrap()
+				.join(format!("wesl-metadata-{}-{name}", std::process::id()));
+			drop(std::fs::remove_dir_all(&path));
+			std::fs::create_dir_all(&path).unwrap();
+			Self(path)
+		}
+
+		pub(crate) fn path(&self) -> &Utf8Path {
+			&self.0
+		}
+
+		/// Write `contents` to `relative`, creating its parent directories, and return its path.
+		pub(crate) fn write(
+			&self,
+			relative: &str,
+			contents: &str,
+		) -> Utf8PathBuf {
+			let path = self.0.join(relative);
+			std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+			std::fs::write(&path, contents).unwrap();
+			path
+		}
+
+		/// Write an executable `wesl` stand-in running `script` and return its path.
+		#[cfg(unix)]
+		pub(crate) fn shim(
+			&self,
+			name: &str,
+			script: &str,
+		) -> PathBuf {
+			use std::os::unix::fs::PermissionsExt as _;
+
+			let path = self.write(name, &format!("#!/bin/sh\n{script}\n"));
+			std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+			path.into_std_path_buf()
+		}
+	}
+
+	impl Drop for TempDir {
+		fn drop(&mut self) {
+			drop(std::fs::remove_dir_all(&self.0));
+		}
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn wesl_version() {
+		let directory = TempDir::new("wesl-version");
+		let mut command = MetadataCommand::new();
+		command.wesl_path(directory.shim(
+			"version",
+			"[ \"$1\" = --version ] && echo \"wesl v0.3.0-beta.1 ($WESL_COMMIT)\"",
+		));
+		command.env("WESL_COMMIT", "4f2c1e0 2026-01-01");
+		assert_eq!(
+			command.wesl_version().unwrap(),
+			Version::parse("0.3.0-beta.1").unwrap()
+		);
+
+		command.wesl_path(directory.shim("invalid-utf8-version", "printf 'wesl \\377 0.2.1\\n'"));
+		assert_eq!(
+			command.wesl_version().unwrap(),
+			Version::parse("0.2.1").unwrap()
+		);
+
+		command.wesl_path(directory.shim("no-version", "echo 'wesl (unknown)'"));
+		assert!(matches!(
+			command.wesl_version().unwrap_err(),
+			Error::WeslVersion { output } if output == "wesl (unknown)\n"
+		));
+
+		command.wesl_path(directory.shim("version-fails", "echo 'no such flag' >&2\nexit 2"));
+		assert!(matches!(
+			command.wesl_version().unwrap_err(),
+			Error::WeslVersion { output } if output == "no such flag\n"
+		));
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn env_clear() {
+		let directory = TempDir::new("env-clear");
+		let mut command = MetadataCommand::new();
+		command
+			.wesl_path(directory.shim("env-clear", "exec /usr/bin/env"))
+			.env("WESL_KEPT", "1")
+			.env_remove("WESL_REMOVED")
+			.env_clear()
+			.envs([("WESL_BULK_A", "a"), ("WESL_BULK_B", "b")]);
+		let output = command.wesl_command().output().unwrap();
+		assert!(output.status.success());
+		let keys: BTreeSet<&str> = from_utf8(&output.stdout)
+			.unwrap()
+			.lines()
+			.filter_map(|line| line.split_once('=').map(|(key, _)| key))
+			// the shell itself sets the working directory
+			.filter(|key| *key != "PWD")
+			.collect();
+		assert_eq!(
+			keys,
+			BTreeSet::from(["WESL_BULK_A", "WESL_BULK_B", "WESL_KEPT"])
+		);
+	}
+
+	#[test]
+	fn parse_stdout() {
+		let metadata = metadata(vec![package("app", "1.0.0")]);
+		let compact = serde_json::to_string(&metadata).unwrap();
+		let pretty = serde_json::to_string_pretty(&metadata).unwrap();
+		for stdout in [
+			format!("{compact}\n"),
+			format!("Resolving dependencies\n{compact}\n"),
+			format!("Resolving dependencies\n{pretty}\n"),
+			format!("{pretty}\nDone\n"),
+		] {
+			assert_eq!(MetadataCommand::parse_stdout(&stdout).unwrap(), metadata);
+		}
+		assert!(matches!(
+			MetadataCommand::parse_stdout("no json here\n"),
+			Err(Error::NoJson)
+		));
+		for stdout in ["", " \n\t\n"] {
+			assert!(matches!(
+				MetadataCommand::parse_stdout(stdout),
+				Err(Error::EmptyOutput)
+			));
+		}
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn empty_output() {
+		let directory = TempDir::new("empty-output");
+		let mut command = MetadataCommand::new();
+		command.wesl_path(directory.shim("empty-output", "echo"));
+		assert!(matches!(command.exec().unwrap_err(), Error::EmptyOutput));
+		command.wesl_path(directory.shim("noise-output", "echo 'Resolving dependencies'"));
+		assert!(matches!(command.exec().unwrap_err(), Error::NoJson));
+	}
+
+	#[test]
+	fn parse_stdout_strict() {
+		let metadata = metadata(vec![package("app", "1.0.0")]);
+		let mut json = serde_json::to_value(&metadata).unwrap();
+		let fields: BTreeSet<&str> = json
+			.as_object()
+			.unwrap()
+			.keys()
+			.map(String::as_str)
+			.collect();
+		assert_eq!(fields, BTreeSet::from(METADATA_FIELDS));
+		let known = json.to_string();
+		json.as_object_mut()
+			.unwrap()
+			.insert("new_field".to_owned(), true.into());
+		let unknown = json.to_string();
+
+		let mut command = MetadataCommand::new();
+		assert_eq!(command.parse_output(&unknown).unwrap(), metadata);
+		command.strict(true);
+		assert_eq!(command.parse_output(&known).unwrap(), metadata);
+		assert!(matches!(
+			command.parse_output(&unknown).unwrap_err(),
+			Error::UnknownField { field } if field == "new_field"
+		));
+	}
+
+	#[test]
+	fn wesl_not_found() {
+		let path = env::temp_dir()
+			.join("wesl-metadata-nonexistent")
+			.join("wesl");
+		let error = MetadataCommand::new().wesl_path(&path).exec().unwrap_err();
+		assert!(matches!(
+			&error,
+			Error::WeslNotFound { attempted, .. } if *attempted == path
+		));
+		assert!(error.to_string().contains("is it installed?"));
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn wesl_metadata_exit_code() {
+		let directory = TempDir::new("wesl-metadata-exit-code");
+		let error = MetadataCommand::new()
+			.wesl_path(directory.shim(
+				"exit-code",
+				"printf '{\"packages\": ['\necho 'bad manifest' >&2\nexit 3",
+			))
+			.exec()
+			.unwrap_err();
+		assert!(matches!(
+			&error,
+			Error::WeslMetadata {
+				exit_code: Some(3),
+				stderr,
+				stdout,
+			} if stderr == "bad manifest\n" && stdout == "{\"packages\": ["
+		));
+		assert_eq!(
+			error.to_string(),
+			"`wesl metadata` exited with exit code 3: bad manifest\n"
+		);
+	}
+
+	#[test]
+	fn package_id_str() {
+		let id = PackageId::from("app@1.0.0");
+		assert_eq!(id, PackageId::from("app@1.0.0".to_owned()));
+		assert_eq!(id.as_str(), "app@1.0.0");
+		assert_eq!(AsRef::<str>::as_ref(&id), "app@1.0.0");
+		assert!(id.starts_with("app@"));
+
+		let versions = BTreeMap::from([(id, "1.0.0")]);
+		assert_eq!(versions.get("app@1.0.0"), Some(&"1.0.0"));
+	}
+
+	#[test]
+	fn package_id_components() {
+		let version = Version::new(1, 2, 3);
+		for source in [None, Some("registry+https://example.com/index")] {
+			let id = PackageId::from_name_version_source("app", &version, source);
+			assert_eq!(
+				id.try_parse_components(),
+				Some(("app".to_owned(), version.clone(), source.map(str::to_owned)))
+			);
+		}
+		assert_eq!(
+			PackageId::from_name_version_source("app", &version, None).repr,
+			"app@1.2.3"
+		);
+		assert_eq!(
+			PackageId::from("path+file:///workspace/app#1.2.3").try_parse_components(),
+			Some((
+				"app".to_owned(),
+				version,
+				Some("path+file:///workspace/app".to_owned())
+			))
+		);
+		assert_eq!(PackageId::from("app").try_parse_components(), None);
+		assert_eq!(PackageId::from("app@latest").try_parse_components(), None);
+	}
+
+	#[test]
+	fn authors_parsed() {
+		let mut package = package("app", "1.0.0");
+		package.authors = vec![
+			"Jane Doe <jane@example.com>".to_owned(),
+			"John Doe".to_owned(),
+			"Broken <jane@example.com".to_owned(),
+			"Nested <<jane@example.com>>".to_owned(),
+		];
+		assert_eq!(
+			package.authors_parsed(),
+			[
+				("Jane Doe".to_owned(), Some("jane@example.com".to_owned())),
+				("John Doe".to_owned(), None),
+				("Broken <jane@example.com".to_owned(), None),
+				("Nested <<jane@example.com>>".to_owned(), None),
+			]
+		);
+	}
+
+	#[test]
+	fn entry_point() {
+		let parse = |extra: &str| -> Package {
+			serde_json::from_str(&format!(
+				r#"{{"name": "app", "version": "1.0.0", "id": "app@1.0.0", "source": null, "dependencies": [], "manifest_path": "/app/wesl.toml"{extra}}}"#
+			))
+			.unwrap()
+		};
+		let targets = r#", "targets": [{"name": "tool", "kind": ["bin"], "src_path": "/app/src/tool.wesl"}, {"name": "app", "kind": ["bin"], "src_path": "/app/src/main.wesl"}]"#;
+
+		let explicit = parse(&format!(r#", "default-entry": "tool"{targets}"#));
+		assert_eq!(explicit.entry_point(), Some("tool"));
+		let fallback = parse(targets);
+		assert_eq!(fallback.default_entry_point, None);
+		assert_eq!(fallback.entry_point(), Some("app"));
+		assert_eq!(parse("").entry_point(), None);
+
+		let written = serde_json::to_string(&explicit).unwrap();
+		assert!(written.contains(r#""default_entry_point":"tool""#));
+		assert_eq!(serde_json::from_str::<Package>(&written).unwrap(), explicit);
+	}
+
+	#[test]
+	fn package_paths() {
+		let mut package = package("app", "1.0.0");
+		package.manifest_path = "/workspace/app/wesl.toml".into();
+		package.readme = Some("docs/README.md".into());
+		assert_eq!(package.manifest_dir(), "/workspace/app");
+		assert_eq!(
+			package.readme(),
+			Some("/workspace/app/docs/README.md".into())
+		);
+		assert_eq!(package.license_file(), None);
+	}
+
+	#[test]
+	fn target_directory() {
+		let directory = TempDir::new("target");
+		let mut metadata = metadata(Vec::new());
+		metadata.target_directory = directory.path().join("nested");
+		metadata.ensure_target_directory().unwrap();
+		assert!(metadata.target_directory.is_dir());
+		// creating it again is not an error
+		metadata.ensure_target_directory().unwrap();
+
+		assert_eq!(
+			metadata.target_subdir("wgsl/app.wgsl"),
+			metadata.target_directory.join("wgsl/app.wgsl")
+		);
+		assert_eq!(
+			metadata.target_subdir("/../wgsl/./app.wgsl"),
+			metadata.target_directory.join("wgsl/app.wgsl")
+		);
+	}
+
+	#[test]
+	fn to_json() {
+		let mut metadata = metadata(vec![package("app", "1.0.0"), package("lib", "2.0.0")]);
+		metadata.resolve = Some(Resolve {
+			nodes: vec![node("app@1.0.0", &["lib@2.0.0"]), node("lib@2.0.0", &[])],
+			root: Some(PackageId::from("app@1.0.0")),
+		});
+		let compact = metadata.to_json().unwrap();
+		let pretty = metadata.to_json_pretty().unwrap();
+		assert!(!compact.contains('\n'));
+		assert!(pretty.contains('\n'));
+		assert_eq!(MetadataCommand::parse(compact).unwrap(), metadata);
+		assert_eq!(MetadataCommand::parse(pretty).unwrap(), metadata);
+	}
+
+	#[test]
+	fn skip_empty_lists() {
+		let json = serde_json::json!({
+			"name": "app",
+			"version": "1.0.0",
+			"id": "app@1.0.0",
+			"source": null,
+			"dependencies": [],
+			"license": null,
+			"license_file": null,
+			"description": null,
+			"manifest_path": "/app/wesl.toml",
+			"readme": null,
+			"repository": null,
+			"homepage": null,
+			"documentation": null,
+		});
+		let mut package: Package = serde_json::from_value(json).unwrap();
+		let written = serde_json::to_value(&package).unwrap();
+		for field in ["authors", "categories", "keywords", "metadata"] {
+			assert!(written.get(field).is_none(), "{field} was written");
+		}
+		assert_eq!(serde_json::from_value::<Package>(written).unwrap(), package);
+
+		package.keywords = vec!["shader".to_owned()];
+		let written = serde_json::to_value(&package).unwrap();
+		assert_eq!(written["keywords"], serde_json::json!(["shader"]));
+	}
+
+	#[test]
+	fn logical_eq() {
+		let mut first = metadata(vec![package("app", "1.0.0")]);
+		first.target_directory = "/home/ci/app/target".into();
+		first.root_package_directory = "/home/ci/app".into();
+		let mut second = first.clone();
+		second.target_directory = r"C:\Users\dev\app\target".into();
+		second.root_package_directory = r"C:\Users\dev\app".into();
+		assert_ne!(first, second);
+		assert!(first.logical_eq(&second));
+
+		second.packages.clear();
+		assert!(!first.logical_eq(&second));
+	}
+
+	#[test]
+	fn packages_matching() {
+		let mut util = package("util", "2.1.0");
+		util.license = Some("MIT".to_owned());
+		let metadata = metadata(vec![package("app", "1.0.0"), package("lib", "2.0.0"), util]);
+		let names: Vec<&str> = metadata
+			.packages_matching(|package| package.version.major == 2 && package.license.is_none())
+			.map(|package| package.name.as_str())
+			.collect();
+		assert_eq!(names, ["lib"]);
+	}
+
+	#[test]
+	fn packages_with_keyword_and_category() {
+		let mut app = package("app", "1.0.0");
+		app.keywords = vec!["Lighting".to_owned()];
+		app.categories = vec!["rendering".to_owned()];
+		let mut lib = package("lib", "1.0.0");
+		lib.keywords = vec!["lighting".to_owned(), "pbr".to_owned()];
+		let metadata = metadata(vec![app, lib, package("util", "1.0.0")]);
+
+		let names = |packages: Vec<&Package>| -> Vec<String> {
+			packages
+				.iter()
+				.map(|package| package.name.clone())
+				.collect()
+		};
+		assert_eq!(
+			names(metadata.packages_with_keyword("LIGHTING").collect()),
+			["app", "lib"]
+		);
+		assert_eq!(
+			names(metadata.packages_with_category("Rendering").collect()),
+			["app"]
+		);
+		assert_eq!(metadata.packages_with_keyword("rendering").count(), 0);
+	}
+
+	#[test]
+	fn duplicate_packages() {
+		let metadata = metadata(vec![
+			package("noise", "2.0.0"),
+			package("app", "1.0.0"),
+			package("noise", "1.0.0"),
+			package("same", "1.0.0"),
+			package("same", "1.0.0"),
+		]);
+		let duplicates = metadata.duplicate_packages();
+		let names: Vec<&str> = duplicates.keys().copied().collect();
+		assert_eq!(names, ["noise"]);
+		let versions: Vec<String> = duplicates["noise"]
+			.iter()
+			.map(|package| package.version.to_string())
+			.collect();
+		assert_eq!(versions, ["1.0.0", "2.0.0"]);
+	}
+
+	#[test]
+	fn reverse_dependencies() {
+		let mut metadata = metadata(Vec::new());
+		assert!(matches!(
+			metadata.reverse_dependencies(),
+			Err(Error::MissingResolve)
+		));
+		let mut tool = node("tool", &["shared"]);
+		tool.renamed_dependencies.push(NodeDependency {
+			name: "common".to_owned(),
+			pkg: PackageId::from("shared"),
+		});
+		metadata.resolve = Some(Resolve {
+			nodes: vec![
+				node("app", &["lib", "tool", "shared"]),
+				node("lib", &["shared"]),
+				tool,
+				node("shared", &[]),
+				node("ping", &["pong", "shared"]),
+				node("pong", &["ping"]),
+			],
+			root: Some(PackageId::from("app")),
+		});
+
+		let reverse = metadata.reverse_dependencies().unwrap();
+		let dependents = |id: &str| -> Vec<&str> {
+			reverse[&PackageId::from(id)]
+				.iter()
+				.map(|id| id.as_str())
+				.collect()
+		};
+		assert_eq!(dependents("shared"), ["app", "lib", "ping", "tool"]);
+		assert_eq!(dependents("app"), Vec::<&str>::new());
+		assert_eq!(dependents("ping"), ["pong"]);
+
+		let transitive = |id: &str| -> Vec<&str> {
+			metadata
+				.transitive_reverse_dependencies(&PackageId::from(id))
+				.unwrap()
+				.into_iter()
+				.map(PackageId::as_str)
+				.collect()
+		};
+		assert_eq!(transitive("shared"), ["app", "lib", "ping", "pong", "tool"]);
+		assert_eq!(transitive("pong"), ["ping", "pong"]);
+		assert_eq!(transitive("lib"), ["app"]);
+	}
+
+	#[test]
+	fn why_duplicated() {
+		let mut metadata = metadata(vec![
+			package("app", "1.0.0"),
+			package("left", "1.0.0"),
+			package("right", "1.0.0"),
+			package("foo", "1.0.0"),
+			package("foo", "2.0.0"),
+			package("unused", "1.0.0"),
+			package("foo", "3.0.0"),
+		]);
+		metadata.resolve = Some(Resolve {
+			nodes: vec![
+				node("app@1.0.0", &["left@1.0.0", "right@1.0.0"]),
+				// a cycle back to the root
+				node("left@1.0.0", &["foo@1.0.0", "app@1.0.0"]),
+				node("right@1.0.0", &["left@1.0.0", "foo@2.0.0"]),
+				node("foo@1.0.0", &[]),
+				node("foo@2.0.0", &["right@1.0.0"]),
+				node("unused@1.0.0", &["foo@3.0.0"]),
+				node("foo@3.0.0", &[]),
+			],
+			root: Some(PackageId::from("app@1.0.0")),
+		});
+		let chains = metadata.why_duplicated("foo");
+		let paths: Vec<Vec<&str>> = chains
+			.iter()
+			.map(|chain| chain.path.iter().map(PackageId::as_str).collect())
+			.collect();
+		assert_eq!(
+			paths,
+			[
+				["app@1.0.0", "left@1.0.0", "foo@1.0.0"],
+				["app@1.0.0", "right@1.0.0", "foo@2.0.0"]
+			]
+		);
+		assert!(metadata.why_duplicated("left").is_empty());
+	}
+
+	#[test]
+	fn workspace_member_packages() {
+		let metadata = MetadataCommand::parse(
+			r#"{
+				"package_manager": "Cargo",
+				"packages": [
+					{"name": "app", "version": "1.0.0", "id": "app@1.0.0", "source": null, "dependencies": [], "manifest_path": "/workspace/app/wesl.toml"},
+					{"name": "lib", "version": "1.0.0", "id": "lib@1.0.0", "source": null, "dependencies": [], "manifest_path": "/workspace/lib/wesl.toml"},
+					{"name": "util", "version": "1.0.0", "id": "util@1.0.0", "source": null, "dependencies": [], "manifest_path": "/registry/util/wesl.toml"}
+				],
+				"workspace_members": ["app@1.0.0", "lib@1.0.0"],
+				"resolve": null,
+				"target_directory": "/workspace/target",
+				"version": 1,
+				"root_package_directory": "/workspace"
+			}"#,
+		)
+		.unwrap();
+		let members: Vec<&str> = metadata
+			.workspace_member_packages()
+			.iter()
+			.map(|package| package.name.as_str())
+			.collect();
+		assert_eq!(members, ["app", "lib"]);
+	}
+
+	#[test]
+	fn package_order_and_hash() {
+		use std::hash::{BuildHasher as _, BuildHasherDefault, DefaultHasher};
+
+		let hasher = BuildHasherDefault::<DefaultHasher>::default();
+		let mut tagged = package("lib", "1.0.0");
+		tagged.metadata = serde_json::json!({"tool": {"b": 2, "a": 1}});
+		let mut reordered = tagged.clone();
+		reordered.metadata = serde_json::from_str(r#"{"tool": {"a": 1, "b": 2}}"#).unwrap();
+		assert_eq!(tagged, reordered);
+		assert_eq!(hasher.hash_one(&tagged), hasher.hash_one(&reordered));
+
+		let mut other = tagged.clone();
+		other.metadata = serde_json::json!({"tool": {"a": 2}});
+		assert_ne!(tagged, other);
+		assert_ne!(tagged.cmp(&other), Ordering::Equal);
+		assert_eq!(tagged.sort_key(), other.sort_key());
+
+		let set = BTreeSet::from([
+			package("lib", "2.0.0"),
+			tagged.clone(),
+			package("app", "1.0.0"),
+			reordered,
+			other,
+			package("lib", "1.0.0"),
+		]);
+		let keys: Vec<String> = set
+			.iter()
+			.map(|package| format!("{}@{}", package.name, package.version))
+			.collect();
+		assert_eq!(
+			keys,
+			[
+				"app@1.0.0",
+				"lib@1.0.0",
+				"lib@1.0.0",
+				"lib@1.0.0",
+				"lib@2.0.0"
+			]
+		);
+		assert!(set.contains(&tagged));
+	}
+
+	#[test]
+	fn authors_and_licenses() {
+		let mut app = package("app", "1.0.0");
+		app.authors = vec!["Jane Doe".to_owned(), "John Doe".to_owned()];
+		app.license = Some("MIT OR Apache-2.0".to_owned());
+		let mut lib = package("lib", "1.0.0");
+		lib.authors = vec!["Jane Doe".to_owned()];
+		lib.license = Some("MIT OR Apache-2.0".to_owned());
+		let mut custom = package("custom", "1.0.0");
+		custom.license_file = Some("LICENSE.txt".into());
+		let metadata = metadata(vec![app, lib, custom, package("unlicensed", "1.0.0")]);
+
+		assert_eq!(
+			metadata.all_authors(),
+			BTreeSet::from(["Jane Doe", "John Doe"])
+		);
+		assert_eq!(
+			metadata.all_licenses(),
+			BTreeSet::from(["MIT OR Apache-2.0"])
+		);
+		let unlicensed: Vec<&str> = metadata
+			.packages_without_license()
+			.map(|package| package.name.as_str())
+			.collect();
+		assert_eq!(unlicensed, ["unlicensed"]);
+	}
+
+	#[test]
+	fn get() {
+		let mut metadata = metadata(
+			(0..5000)
+				.map(|index| package(&format!("package-{index}"), "1.0.0"))
+				.collect(),
+		);
+		let id = PackageId {
+			repr: "package-4321@1.0.0".to_owned(),
+		};
+		// without an index, lookups scan
+		assert_eq!(metadata.get(&id).unwrap().name, "package-4321");
+
+		metadata.reindex();
+		assert_eq!(metadata.package_index.positions.len(), 5000);
+		for package in &metadata.packages {
+			assert_eq!(metadata.get(&package.id), Some(package));
+		}
+		assert_eq!(
+			metadata.get(&PackageId {
+				repr: "missing".to_owned()
+			}),
+			None
+		);
+
+		// with as many packages as indexed, a miss does not scan, so an id changed in place
+		// is only found after reindexing
+		let original = metadata.packages[0].id.clone();
+		let renamed = PackageId::from("renamed");
+		metadata.packages[0].id = renamed.clone();
+		assert_eq!(metadata.get(&original), None);
+		assert_eq!(metadata.get(&renamed), None);
+		metadata.reindex();
+		assert_eq!(metadata.get(&renamed).unwrap().name, "package-0");
+		metadata.packages[0].id = original;
+		metadata.reindex();
+
+		// a stale index still finds the right package
+		metadata.packages.push(package("added", "1.0.0"));
+		assert_eq!(
+			metadata.get(&PackageId::from("added@1.0.0")).unwrap().name,
+			"added"
+		);
+		metadata.packages.pop();
+		metadata.packages.reverse();
+		metadata.shrink_to_fit();
+		assert_eq!(metadata[&id].name, "package-4321");
+		metadata.reindex();
+		assert_eq!(metadata.package_index.positions.get(&id), Some(&678));
+	}
+
+	#[test]
+	fn manifest_not_found() {
+		let wesl = env::temp_dir()
+			.join("wesl-metadata-nonexistent")
+			.join("wesl");
+		let missing = env::temp_dir()
+			.join("wesl-metadata-nonexistent")
+			.join("wesl.toml");
+		let error = MetadataCommand::new()
+			.wesl_path(&wesl)
+			.manifest_path(&missing)
+			.exec()
+			.unwrap_err();
+		assert!(matches!(error, Error::ManifestNotFound { path } if path == missing));
+
+		let error = MetadataCommand::new()
+			.wesl_path(&wesl)
+			.manifest_path(&missing)
+			.check_manifest_path(false)
+			.exec()
+			.unwrap_err();
+		assert!(matches!(error, Error::WeslNotFound { .. }));
+
+		let error = MetadataCommand::new()
+			.wesl_path(&wesl)
+			.current_dir(env!("CARGO_MANIFEST_DIR"))
+			.manifest_path("Cargo.toml")
+			.exec()
+			.unwrap_err();
+		assert!(matches!(error, Error::WeslNotFound { .. }));
+	}
+
+	#[test]
+	fn exec_for_manifest() {
+		let missing = env::temp_dir()
+			.join("wesl-metadata-nonexistent")
+			.join("wesl.toml");
+		assert!(matches!(
+			MetadataCommand::exec_for_manifest(&missing).unwrap_err(),
+			Error::ManifestNotFound { path } if path == missing
+		));
+	}
+
+	#[test]
+	fn manifest_directory() {
+		let directory = TempDir::new("manifest");
+		directory.write("app/wesl.toml", "");
+		std::fs::create_dir_all(directory.path().join("empty")).unwrap();
+		let root = directory.path().as_std_path();
+		let wesl = root.join("nonexistent").join("wesl");
+
+		let mut command = MetadataCommand::new();
+		command.wesl_path(&wesl).manifest_path(root.join("app"));
+		assert_eq!(
+			command_args(&command),
+			[
+				"metadata",
+				"--manifest-path",
+				root.join("app/wesl.toml").to_str().unwrap()
+			]
+		);
+		assert!(matches!(
+			command.exec().unwrap_err(),
+			Error::WeslNotFound { .. }
+		));
+
+		command.current_dir(root).manifest_path("app");
+		assert_eq!(
+			command_args(&command),
+			[
+				"metadata",
+				"--manifest-path",
+				Path::new("app").join("wesl.toml").to_str().unwrap()
+			]
+		);
+
+		command.manifest_path("empty");
+		assert!(matches!(
+			command.exec().unwrap_err(),
+			Error::ManifestNotFound { path } if path == root.join("empty/wesl.toml")
+		));
+
+		command.manifest_path("app/wesl.toml");
+		assert_eq!(
+			command_args(&command),
+			["metadata", "--manifest-path", "app/wesl.toml"]
+		);
+	}
+
+	#[test]
+	fn try_from_json() {
+		let metadata = metadata(vec![package("app", "1.0.0")]);
+		let value = serde_json::Value::try_from(metadata.clone()).unwrap();
+		let json = value.to_string();
+
+		let from_bytes = Metadata::try_from(json.as_bytes()).unwrap();
+		assert_eq!(from_bytes, metadata);
+		assert_eq!(from_bytes.package_index.positions.len(), 1);
+		let from_value = Metadata::try_from(value).unwrap();
+		assert_eq!(from_value, metadata);
+		assert_eq!(from_value.package_index.positions.len(), 1);
+
+		assert!(Metadata::try_from(&b"{\xff}"[..]).unwrap_err().is_utf8());
+		assert!(Metadata::try_from(&b"{}"[..]).unwrap_err().is_json());
+		assert!(
+			Metadata::try_from(serde_json::json!({"packages": 1}))
+				.unwrap_err()
+				.is_json()
+		);
+	}
+
+	#[test]
+	fn editions() {
+		assert!(Edition::all().is_sorted());
+		assert_eq!(Edition::Wgsl.year(), None);
+		assert_eq!(Edition::WeslUnstable2025.year(), Some(2025));
+		assert_eq!(
+			Edition::try_from_year(2025),
+			Some(Edition::WeslUnstable2025)
+		);
+		assert_eq!(Edition::try_from_year(2024), None);
+		for edition in Edition::all() {
+			let json = serde_json::to_string(edition).unwrap();
+			assert_eq!(json, format!("\"{}\"", edition.as_str()));
+			assert_eq!(serde_json::from_str::<Edition>(&json).unwrap(), *edition);
+		}
+	}
+
+	#[test]
+	fn into_iter() {
+		let mut metadata = metadata(vec![package("app", "1.0.0"), package("lib", "1.0.0")]);
+		metadata.resolve = Some(Resolve {
+			nodes: vec![node("app@1.0.0", &["lib@1.0.0"]), node("lib@1.0.0", &[])],
+			root: None,
+		});
+
+		let mut names = Vec::new();
+		for package in &metadata {
+			names.push(package.name.as_str());
+		}
+		assert_eq!(names, ["app", "lib"]);
+
+		let resolve = metadata.resolve.clone().unwrap();
+		assert_eq!((&resolve).into_iter().count(), 2);
+		let ids: Vec<PackageId> = resolve.into_iter().map(|node| node.id).collect();
+		assert_eq!(ids.first().unwrap().repr, "app@1.0.0");
+
+		let names: Vec<String> = metadata
+			.into_iter()
+			.filter(|package| package.name != "app")
+			.map(|package| package.name)
+			.collect();
+		assert_eq!(names, ["lib"]);
+	}
+
+	#[test]
+	fn metadata_as() {
+		#[derive(Debug, Deserialize, PartialEq, Eq)]
+		struct SomePackageMetadata {
+			some_value: i32,
+		}
+
+		#[derive(Debug, Deserialize, PartialEq, Eq)]
+		struct OptionalMetadata {
+			some_value: Option<i32>,
+		}
+
+		let mut package = package("app", "1.0.0");
+		package.metadata_as::<SomePackageMetadata>().unwrap_err();
+		assert_eq!(
+			package.metadata_as::<Option<OptionalMetadata>>().unwrap(),
+			None
+		);
+
+		package.metadata = serde_json::json!({ "some_value": 42 });
+		assert_eq!(
+			package.metadata_as::<SomePackageMetadata>().unwrap(),
+			SomePackageMetadata { some_value: 42 }
+		);
+		assert_eq!(
+			package.metadata_as::<OptionalMetadata>().unwrap(),
+			OptionalMetadata {
+				some_value: Some(42)
+			}
+		);
+	}
+
+	#[test]
+	fn node_into_iter() {
+		let node = node("app", &["lib", "util"]);
+		let mut dependencies = Vec::new();
+		for dependency in &node {
+			dependencies.push(dependency.repr.as_str());
+		}
+		assert_eq!(dependencies, ["lib", "util"]);
+		assert_eq!(
+			node.into_iter().last(),
+			Some(PackageId {
+				repr: "util".to_owned()
+			})
+		);
+	}
+
+	#[test]
+	fn target_kind() {
+		let lib: Target = serde_json::from_str(
+			r#"{"name":"shaders","kind":["lib"],"src_path":"/shaders/src/lib.wesl"}"#,
+		)
+		.unwrap();
+		assert!(lib.is_lib());
+		assert!(!lib.is_bin());
+		assert!(!lib.is_example());
+
+		let bin: Target = serde_json::from_str(
+			r#"{"name":"main","kind":["bin"],"src_path":"/shaders/src/main.wesl"}"#,
+		)
+		.unwrap();
+		assert!(bin.is_bin());
+		assert!(!bin.is_lib());
+	}
+
+	#[test]
+	fn dependency_by_name() {
+		let mut package = package("app", "1.0.0");
+		package.dependencies = vec![
+			serde_json::from_str(r#"{"name":"lib","rename":null,"path":null}"#).unwrap(),
+			serde_json::from_str(r#"{"name":"util","rename":"helpers","path":null}"#).unwrap(),
+		];
+		fn name(dependency: Option<&Dependency>) -> Option<&str> {
+			dependency.map(Dependency::effective_name)
+		}
+		assert_eq!(name(package.dependency_by_name("lib")), Some("lib"));
+		assert_eq!(name(package.dependency_by_name("util")), Some("helpers"));
+		assert_eq!(name(package.dependency_by_name("helpers")), Some("helpers"));
+		assert_eq!(name(package.dependency_by_name("missing")), None);
+		assert_eq!(
+			name(package.dependency_by_effective_name("lib")),
+			Some("lib")
+		);
+		assert_eq!(
+			name(package.dependency_by_effective_name("helpers")),
+			Some("helpers")
+		);
+		assert_eq!(name(package.dependency_by_effective_name("util")), None);
+	}
+
+	#[test]
+	fn summary() {
+		let mut metadata = metadata(vec![package("app", "1.0.0"), package("lib", "2.0.0")]);
+		assert_eq!(
+			metadata.summary(),
+			"packages: 2\nroot: none\npackage manager: Cargo\ntarget directory: /target"
+		);
+		metadata.resolve = Some(Resolve {
+			nodes: vec![node("app@1.0.0", &["lib@2.0.0"]), node("lib@2.0.0", &[])],
+			root: Some(PackageId::from("app@1.0.0")),
+		});
+		assert!(metadata.summary().contains("root: app 1.0.0\n"));
+		for manager in [PackageManager::Npm, PackageManager::Cargo] {
+			assert_eq!(serde_json::to_value(manager).unwrap(), manager.as_str());
+		}
+	}
+
+	#[test]
+	fn has_feature() {
+		let mut package = package("shaders", "1.0.0");
+		package.features = BTreeMap::from([
+			("default".to_owned(), vec!["lights".to_owned()]),
+			("lights".to_owned(), vec!["dep:noise".to_owned()]),
+			("shadows".to_owned(), vec!["lights".to_owned()]),
+		]);
+		package.dependencies = vec![
+			serde_json::from_str(r#"{"name":"noise","rename":null,"path":null,"optional":true}"#)
+				.unwrap(),
+			serde_json::from_str(r#"{"name":"blur","rename":null,"path":null,"optional":true}"#)
+				.unwrap(),
+		];
+		package.targets = vec![
+			serde_json::from_str(
+				r#"{"name":"shadows","kind":["example"],"required-features":["shadows","blur"],"src_path":"/shaders/examples/shadows.wesl"}"#,
+			)
+			.unwrap(),
+		];
+		assert_eq!(package.targets[0].required_features, ["shadows", "blur"]);
+		for target in &package.targets {
+			assert!(
+				target
+					.required_features
+					.iter()
+					.all(|feature| package.has_feature(feature))
+			);
+		}
+		assert!(package.has_feature("shadows"));
+		assert!(package.has_feature("blur"));
+		assert!(!package.has_feature("fog"));
+		// `dep:noise` hides the implicit feature of the optional dependency
+		assert!(!package.has_feature("noise"));
+	}
+
+	#[test]
+	fn target_artifact_path() {
+		let target: Target = serde_json::from_str(
+			r#"{"name":"shaders","kind":["lib","example"],"crate_types":["lib","wgsl"],"src_path":"/shaders/src/lib.wesl"}"#,
+		)
+		.unwrap();
+		assert_eq!(target.kind, ["lib", "example"]);
+		assert_eq!(target.crate_types, ["lib", "wgsl"]);
+
+		let metadata = metadata(Vec::new());
+		assert_eq!(
+			target.expected_artifact_path(&metadata, "debug"),
+			Some("/target/debug/shaders.wgsl".into())
+		);
+		assert_eq!(
+			target.expected_artifact_path(&metadata, "release"),
+			Some("/target/release/shaders.wgsl".into())
+		);
+
+		let example: Target = serde_json::from_str(
+			r#"{"name":"demo","kind":["example"],"src_path":"/shaders/examples/demo.wesl"}"#,
+		)
+		.unwrap();
+		assert!(example.crate_types.is_empty());
+		assert_eq!(
+			example.expected_artifact_path(&metadata, "debug"),
+			Some("/target/debug/examples/demo.wgsl".into())
+		);
+
+		let custom: Target = serde_json::from_str(
+			r#"{"name":"build","kind":["custom-build"],"src_path":"/shaders/build.rs"}"#,
+		)
+		.unwrap();
+		assert_eq!(custom.expected_artifact_path(&metadata, "debug"), None);
+	}
 }
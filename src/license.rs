@@ -0,0 +1,517 @@
+//! This module contains [`Package::parsed_license`], [`Package::license_files_all`],
+//! [`Metadata::license_summary`] and the SPDX expression parser they use.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use camino::Utf8PathBuf;
+use serde::Serialize;
+
+use crate::{Metadata, Package, PackageId};
+
+/// A parsed [SPDX license expression](https://spdx.github.io/spdx-spec/v2.3/SPDX-license-expressions/),
+/// see [`Package::parsed_license`].
+///
+/// The identifiers are only checked for their syntax, not against the SPDX license list.
+/// Its `Display` form is normalized, with uppercase operators and only the parentheses
+/// that are needed.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+pub enum LicenseExpression {
+	/// A license identifier, e.g. `MIT`, `GPL-2.0+` or `LicenseRef-Custom`.
+	License(String),
+	/// A license with an exception, e.g. `Apache-2.0 WITH LLVM-exception`.
+	With {
+		/// The license identifier.
+		license: String,
+		/// The exception identifier.
+		exception: String,
+	},
+	/// At least two expressions that all apply.
+	And(Vec<Self>),
+	/// At least two expressions of which one can be chosen.
+	Or(Vec<Self>),
+}
+
+impl LicenseExpression {
+	/// Parse an SPDX license expression.
+	///
+	/// The operators `AND`, `OR` and `WITH` are accepted in any case, and `/` is accepted as
+	/// the deprecated form of `OR`, as in `MIT/Apache-2.0`. Parentheses can be nested up to
+	/// 64 levels deep.
+	pub fn parse(expression: &str) -> Result<Self, LicenseParseError> {
+		Parser::new(expression)?.parse()
+	}
+
+	/// Write this expression, parenthesized if it is an `OR` inside an `AND`.
+	fn fmt_operand(
+		&self,
+		formatter: &mut fmt::Formatter<'_>,
+	) -> fmt::Result {
+		if matches!(self, Self::Or(_)) {
+			write!(formatter, "({self})")
+		} else {
+			write!(formatter, "{self}")
+		}
+	}
+}
+
+impl fmt::Display for LicenseExpression {
+	fn fmt(
+		&self,
+		formatter: &mut fmt::Formatter<'_>,
+	) -> fmt::Result {
+		match self {
+			Self::License(license) => formatter.write_str(license),
+			Self::With { license, exception } => write!(formatter, "{license} WITH {exception}"),
+			Self::And(operands) => {
+				for (position, operand) in operands.iter().enumerate() {
+					if position > 0 {
+						formatter.write_str(" AND ")?;
+					}
+					operand.fmt_operand(formatter)?;
+				}
+				Ok(())
+			},
+			Self::Or(operands) => {
+				for (position, operand) in operands.iter().enumerate() {
+					if position > 0 {
+						formatter.write_str(" OR ")?;
+					}
+					write!(formatter, "{operand}")?;
+				}
+				Ok(())
+			},
+		}
+	}
+}
+
+/// Error returned when a license expression is not valid SPDX, see [`LicenseExpression::parse`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, thiserror::Error)]
+#[error("invalid license expression `{expression}` at byte {position}: {message}")]
+#[non_exhaustive]
+pub struct LicenseParseError {
+	/// The expression that failed to parse.
+	pub expression: String,
+
+	/// The byte offset in `expression` at which parsing failed.
+	pub position: usize,
+
+	/// What was wrong.
+	pub message: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Token<'text> {
+	Open,
+	Close,
+	Slash,
+	Word(&'text str),
+}
+
+/// The deepest nesting of parentheses accepted by [`LicenseExpression::parse`], which bounds
+/// the recursion of the parser.
+const MAX_NESTING: usize = 64;
+
+struct Parser<'text> {
+	expression: &'text str,
+	/// The tokens with their byte offsets.
+	tokens: Vec<(usize, Token<'text>)>,
+	next: usize,
+	/// The number of parentheses around the next token.
+	nesting: usize,
+}
+
+/// Whether `character` can be part of an identifier.
+const fn is_identifier_character(character: char) -> bool {
+	character.is_ascii_alphanumeric() || matches!(character, '-' | '.' | '+' | ':')
+}
+
+/// Whether `word` is a valid license or exception identifier.
+fn is_identifier(word: &str) -> bool {
+	let id = word.strip_suffix('+').unwrap_or(word);
+	if word.contains(':') || word.contains("LicenseRef-") {
+		// `LicenseRef-name` or `DocumentRef-name:LicenseRef-name`
+		let reference = match word.split_once(':') {
+			Some((document, reference)) => {
+				if document.len() <= "DocumentRef-".len() || !document.starts_with("DocumentRef-") {
+					return false;
+				}
+				reference
+			},
+			None => word,
+		};
+		return reference.len() > "LicenseRef-".len()
+			&& reference.starts_with("LicenseRef-")
+			&& !reference.contains([':', '+']);
+	}
+	!id.is_empty() && !id.contains('+')
+}
+
+impl<'text> Parser<'text> {
+	fn new(expression: &'text str) -> Result<Self, LicenseParseError> {
+		let mut tokens = Vec::new();
+		let mut word_start = None;
+		for (position, character) in expression.char_indices().chain([(expression.len(), ' ')]) {
+			if is_identifier_character(character) {
+				word_start.get_or_insert(position);
+				continue;
+			}
+			if let Some(start) = word_start.take() {
+				tokens.push((start, Token::Word(&expression[start..position])));
+			}
+			let token = match character {
+				'(' => Token::Open,
+				')' => Token::Close,
+				'/' => Token::Slash,
+				_ if character.is_whitespace() => continue,
+				_ => {
+					return Err(LicenseParseError {
+						expression: expression.to_owned(),
+						position,
+						message: format!("unexpected character `{character}`"),
+					});
+				},
+			};
+			tokens.push((position, token));
+		}
+		Ok(Self {
+			expression,
+			tokens,
+			next: 0,
+			nesting: 0,
+		})
+	}
+
+	fn error(
+		&self,
+		message: &str,
+	) -> LicenseParseError {
+		LicenseParseError {
+			expression: self.expression.to_owned(),
+			position: self
+				.tokens
+				.get(self.next)
+				.map_or(self.expression.len(), |(position, _)| *position),
+			message: message.to_owned(),
+		}
+	}
+
+	fn peek(&self) -> Option<Token<'text>> {
+		self.tokens.get(self.next).map(|(_, token)| *token)
+	}
+
+	/// Skip the next token if it is the operator `operator`, in any case.
+	fn operator(
+		&mut self,
+		operator: &str,
+	) -> bool {
+		let matched =
+			matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(operator));
+		if matched {
+			self.next += 1;
+		}
+		matched
+	}
+
+	fn parse(mut self) -> Result<LicenseExpression, LicenseParseError> {
+		if self.tokens.is_empty() {
+			return Err(self.error("the expression is empty"));
+		}
+		let expression = self.parse_or()?;
+		if self.next < self.tokens.len() {
+			return Err(self.error("expected `AND`, `OR` or `WITH`"));
+		}
+		Ok(expression)
+	}
+
+	fn parse_or(&mut self) -> Result<LicenseExpression, LicenseParseError> {
+		let mut operands = vec![self.parse_and()?];
+		loop {
+			if self.peek() == Some(Token::Slash) {
+				self.next += 1;
+			} else if !self.operator("OR") {
+				break;
+			}
+			operands.push(self.parse_and()?);
+		}
+		Ok(Self::combine(operands, LicenseExpression::Or))
+	}
+
+	fn parse_and(&mut self) -> Result<LicenseExpression, LicenseParseError> {
+		let mut operands = vec![self.parse_with()?];
+		while self.operator("AND") {
+			operands.push(self.parse_with()?);
+		}
+		Ok(Self::combine(operands, LicenseExpression::And))
+	}
+
+	/// A single operand as it is, or several combined with `operator`.
+	fn combine(
+		mut operands: Vec<LicenseExpression>,
+		operator: fn(Vec<LicenseExpression>) -> LicenseExpression,
+	) -> LicenseExpression {
+		if operands.len() == 1 {
+			operands.remove(0)
+		} else {
+			operator(operands)
+		}
+	}
+
+	fn parse_with(&mut self) -> Result<LicenseExpression, LicenseParseError> {
+		if self.peek() == Some(Token::Open) {
+			if self.nesting == MAX_NESTING {
+				return Err(self.error("parentheses are nested too deeply"));
+			}
+			self.next += 1;
+			self.nesting += 1;
+			let expression = self.parse_or()?;
+			if self.peek() != Some(Token::Close) {
+				return Err(self.error("expected `)`"));
+			}
+			self.next += 1;
+			self.nesting -= 1;
+			return Ok(expression);
+		}
+		let license = self.identifier("expected a license identifier")?;
+		if !self.operator("WITH") {
+			return Ok(LicenseExpression::License(license));
+		}
+		let exception = self.identifier("expected an exception identifier")?;
+		Ok(LicenseExpression::With { license, exception })
+	}
+
+	fn identifier(
+		&mut self,
+		message: &str,
+	) -> Result<String, LicenseParseError> {
+		match self.peek() {
+			Some(Token::Word(word))
+				if is_identifier(word)
+					&& !["AND", "OR", "WITH"]
+						.iter()
+						.any(|operator| word.eq_ignore_ascii_case(operator)) =>
+			{
+				self.next += 1;
+				Ok(word.to_owned())
+			},
+			Some(_) | None => Err(self.error(message)),
+		}
+	}
+}
+
+/// The names of license files probed by [`Package::license_files_all`], in order.
+const CONVENTIONAL_LICENSE_FILES: [&str; 2] = ["LICENSE", "LICENSE.md"];
+
+/// The licenses of the packages of a [`Metadata`], see [`Metadata::license_summary`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct LicenseSummary {
+	/// The packages with a valid `license`, by normalized expression.
+	pub by_expression: BTreeMap<String, Vec<PackageId>>,
+
+	/// The packages with an invalid `license`, with the reason.
+	pub invalid: BTreeMap<PackageId, LicenseParseError>,
+
+	/// The packages with only a `license_file`, with its full path.
+	pub license_files: BTreeMap<PackageId, Utf8PathBuf>,
+
+	/// The packages with neither a `license` nor a `license_file`.
+	pub unlicensed: Vec<PackageId>,
+}
+
+impl Package {
+	/// Parse the `license` of this package as an SPDX expression, see
+	/// [`LicenseExpression::parse`].
+	#[must_use]
+	pub fn parsed_license(&self) -> Option<Result<LicenseExpression, LicenseParseError>> {
+		self.license.as_deref().map(LicenseExpression::parse)
+	}
+
+	/// Full paths to every license file of this package.
+	///
+	/// This is the [`Self::license_file`] from the manifest if there is one, followed by
+	/// the conventionally named files that exist in [`Self::manifest_dir`], in this order:
+	/// `LICENSE`, then `LICENSE.md`. A path is only listed once.
+	#[must_use]
+	pub fn license_files_all(&self) -> Vec<Utf8PathBuf> {
+		let mut files: Vec<Utf8PathBuf> = self.license_file().into_iter().collect();
+		for name in CONVENTIONAL_LICENSE_FILES {
+			let file = self.manifest_dir().join(name);
+			if file.is_file() && !files.contains(&file) {
+				files.push(file);
+			}
+		}
+		files
+	}
+}
+
+impl Metadata {
+	/// Group the packages by license for an audit.
+	///
+	/// Packages with a `license` are grouped by its normalized expression, or listed as
+	/// invalid if it does not parse. The other packages are listed with their license file,
+	/// see [`Package::license_file`], or as unlicensed. Packages keep their order.
+	#[must_use]
+	pub fn license_summary(&self) -> LicenseSummary {
+		let mut summary = LicenseSummary::default();
+		for package in &self.packages {
+			let id = package.id.clone();
+			match (package.parsed_license(), package.license_file()) {
+				(Some(Ok(expression)), _) => summary
+					.by_expression
+					.entry(expression.to_string())
+					.or_default()
+					.push(id),
+				(Some(Err(error)), _) => {
+					summary.invalid.insert(id, error);
+				},
+				(None, Some(file)) => {
+					summary.license_files.insert(id, file);
+				},
+				(None, None) => summary.unlicensed.push(id),
+			}
+		}
+		summary
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tests::{TempDir, metadata, package};
+
+	#[test]
+	fn parse() {
+		for (expression, normalized) in [
+			("MIT", "MIT"),
+			("MIT OR Apache-2.0", "MIT OR Apache-2.0"),
+			("MIT/Apache-2.0", "MIT OR Apache-2.0"),
+			(
+				"(MIT or Apache-2.0) and Zlib",
+				"(MIT OR Apache-2.0) AND Zlib",
+			),
+			("MIT OR (Apache-2.0 AND Zlib)", "MIT OR Apache-2.0 AND Zlib"),
+			(
+				"Apache-2.0 WITH LLVM-exception OR GPL-2.0+",
+				"Apache-2.0 WITH LLVM-exception OR GPL-2.0+",
+			),
+			(
+				"DocumentRef-spdx-tool:LicenseRef-Custom",
+				"DocumentRef-spdx-tool:LicenseRef-Custom",
+			),
+		] {
+			assert_eq!(
+				LicenseExpression::parse(expression).unwrap().to_string(),
+				normalized,
+				"{expression}"
+			);
+		}
+		assert_eq!(
+			LicenseExpression::parse("MIT OR Apache-2.0").unwrap(),
+			LicenseExpression::Or(vec![
+				LicenseExpression::License("MIT".to_owned()),
+				LicenseExpression::License("Apache-2.0".to_owned()),
+			])
+		);
+
+		for (expression, position) in [
+			("", 0),
+			("MIT OR", 6),
+			("MIT Apache-2.0", 4),
+			("(MIT", 4),
+			("MIT)", 3),
+			("Proprietary, see LICENSE", 11),
+			("MIT WITH (Apache-2.0)", 9),
+			("AND", 0),
+			("GPL+2.0", 0),
+		] {
+			assert_eq!(
+				LicenseExpression::parse(expression).unwrap_err().position,
+				position,
+				"{expression}"
+			);
+		}
+
+		let nested = |depth: usize| format!("{}MIT{}", "(".repeat(depth), ")".repeat(depth));
+		assert_eq!(
+			LicenseExpression::parse(&nested(MAX_NESTING)).unwrap(),
+			LicenseExpression::License("MIT".to_owned())
+		);
+		let error = LicenseExpression::parse(&nested(100_000)).unwrap_err();
+		assert_eq!(error.position, MAX_NESTING);
+		assert_eq!(error.message, "parentheses are nested too deeply");
+	}
+
+	#[test]
+	fn license_files_all() {
+		let directory = TempDir::new("license");
+		let root = directory.path();
+		directory.write("legal/COPYING", "");
+		directory.write("LICENSE", "");
+		let mut package = package("app", "1.0.0");
+		package.manifest_path = root.join("wesl.toml");
+		assert_eq!(package.license_files_all(), [root.join("LICENSE")]);
+
+		package.license_file = Some("legal/COPYING".into());
+		directory.write("LICENSE.md", "");
+		assert_eq!(
+			package.license_files_all(),
+			[
+				root.join("legal/COPYING"),
+				root.join("LICENSE"),
+				root.join("LICENSE.md")
+			]
+		);
+
+		package.license_file = Some("LICENSE".into());
+		assert_eq!(
+			package.license_files_all(),
+			[root.join("LICENSE"), root.join("LICENSE.md")]
+		);
+	}
+
+	#[test]
+	fn license_summary() {
+		let mut dual = package("dual", "1.0.0");
+		dual.license = Some("MIT OR Apache-2.0".to_owned());
+		let mut legacy = package("legacy", "1.0.0");
+		legacy.license = Some("MIT/Apache-2.0".to_owned());
+		let mut custom = package("custom", "1.0.0");
+		custom.license = Some("see LICENSE.txt".to_owned());
+		let mut file = package("file", "1.0.0");
+		file.license_file = Some("LICENSE.txt".into());
+		let unlicensed = package("unlicensed", "1.0.0");
+		let metadata = metadata(vec![dual, legacy, custom, file, unlicensed]);
+
+		let summary = metadata.license_summary();
+		assert_eq!(
+			summary.by_expression,
+			BTreeMap::from([(
+				"MIT OR Apache-2.0".to_owned(),
+				vec![
+					PackageId::from("dual@1.0.0"),
+					PackageId::from("legacy@1.0.0")
+				]
+			)])
+		);
+		assert_eq!(
+			summary.invalid[&PackageId::from("custom@1.0.0")].expression,
+			"see LICENSE.txt"
+		);
+		assert_eq!(
+			summary.license_files,
+			BTreeMap::from([(
+				PackageId::from("file@1.0.0"),
+				Utf8PathBuf::from("/file/LICENSE.txt")
+			)])
+		);
+		assert_eq!(summary.unlicensed, [PackageId::from("unlicensed@1.0.0")]);
+
+		let json = serde_json::to_value(&summary).unwrap();
+		assert_eq!(
+			json["license_files"]["file@1.0.0"],
+			serde_json::json!("/file/LICENSE.txt")
+		);
+		assert_eq!(json["invalid"]["custom@1.0.0"]["position"], 4);
+	}
+}
@@ -0,0 +1,388 @@
+//! This module contains [`Package::dependency_decl_span`] and the span-preserving manifest
+//! scanner it uses.
+
+use std::fs;
+
+use crate::{Package, Result};
+
+/// The names of the tables declaring dependencies.
+const DEPENDENCY_TABLES: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// The location of a declaration in a manifest, see [`Package::dependency_decl_span`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct ManifestSpan {
+	/// The byte offset of the start of the declaration.
+	pub start: usize,
+
+	/// The byte offset just after the end of the declaration, excluding trailing comments.
+	pub end: usize,
+
+	/// The line of `start`, starting at 1.
+	pub line: usize,
+
+	/// The column of `start` in characters, starting at 1.
+	pub column: usize,
+}
+
+impl ManifestSpan {
+	fn new(
+		manifest: &str,
+		start: usize,
+		end: usize,
+	) -> Self {
+		let before = &manifest[..start];
+		let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+		Self {
+			start,
+			end,
+			line: before.matches('\n').count() + 1,
+			column: before[line_start..].chars().count() + 1,
+		}
+	}
+}
+
+impl Package {
+	/// Locate the declaration of the dependency `key` in the manifest of this package, e.g. to
+	/// edit its version requirement.
+	///
+	/// `key` is the key the dependency is declared with, which is its
+	/// [`rename`](crate::Dependency::rename) if it has one, otherwise its name. The first
+	/// declaration in `[dependencies]`, `[dev-dependencies]`, `[build-dependencies]` or one
+	/// of their `[target.<cfg>]` variants is found, in any of these forms:
+	///
+	/// ```toml
+	/// [dependencies]
+	/// string = "1.0"
+	/// inline = { version = "1.0", features = ["extra"] }
+	/// dotted.version = "1.0"
+	///
+	/// [dependencies.table]
+	/// version = "1.0"
+	/// ```
+	///
+	/// The span covers the whole declaration: the key and value, the consecutive lines of the
+	/// dotted form, or the header and the entries of the table form. A dotted declaration
+	/// interrupted by another entry ends before that entry.
+	///
+	/// Returns `None` if the manifest does not declare `key`, and an error if it cannot be read.
+	pub fn dependency_decl_span(
+		&self,
+		key: &str,
+	) -> Result<Option<ManifestSpan>> {
+		let manifest = fs::read_to_string(&self.manifest_path)?;
+		Ok(find_dependency(&manifest, key))
+	}
+}
+
+/// The span of the first declaration of the dependency `key` in `manifest`.
+fn find_dependency(
+	manifest: &str,
+	key: &str,
+) -> Option<ManifestSpan> {
+	let mut scanner = Scanner {
+		text: manifest,
+		position: 0,
+	};
+	let mut table = Vec::new();
+	let mut found: Option<(Vec<String>, usize, usize)> = None;
+	loop {
+		scanner.skip_trivia();
+		let start = scanner.position;
+		let Some(first) = scanner.peek() else {
+			break;
+		};
+		let path = if first == b'[' {
+			table = scanner.header();
+			table.clone()
+		} else {
+			let keys = scanner.key_path();
+			if keys.is_empty() || scanner.peek() != Some(b'=') {
+				// not a key/value pair, skip the line
+				scanner.skip_line();
+				continue;
+			}
+			scanner.position += 1;
+			scanner.value();
+			table.iter().cloned().chain(keys).collect()
+		};
+		let end = scanner.position;
+		let prefix = dependency_prefix(&path, key);
+		match (&mut found, prefix) {
+			(None, Some(prefix)) => found = Some((prefix, start, end)),
+			(None, None) => {},
+			(Some((declared, _, declared_end)), Some(prefix)) if *declared == prefix => {
+				*declared_end = end;
+			},
+			// the declaration ends at the first entry that is not part of it
+			(Some(_), _) => break,
+		}
+	}
+	found.map(|(_, start, end)| ManifestSpan::new(manifest, start, end))
+}
+
+/// The path of the declaration of `key` if `path` is part of it, e.g. `dependencies.key`
+/// for `dependencies.key.version`.
+fn dependency_prefix(
+	path: &[String],
+	key: &str,
+) -> Option<Vec<String>> {
+	// `dependencies.key` or `target.<cfg>.dependencies.key`
+	let table = match path.first().map(String::as_str) {
+		Some("target") => 2,
+		_ => 0,
+	};
+	let is_declaration = path
+		.get(table)
+		.is_some_and(|name| DEPENDENCY_TABLES.contains(&name.as_str()))
+		&& path.get(table + 1).is_some_and(|name| name == key);
+	is_declaration.then(|| path[..table + 2].to_vec())
+}
+
+/// A cursor over TOML text that keeps byte offsets.
+struct Scanner<'text> {
+	text: &'text str,
+	position: usize,
+}
+
+impl Scanner<'_> {
+	fn peek(&self) -> Option<u8> {
+		self.text.as_bytes().get(self.position).copied()
+	}
+
+	fn skip_spaces(&mut self) {
+		while matches!(self.peek(), Some(b' ' | b'\t')) {
+			self.position += 1;
+		}
+	}
+
+	/// Skip to the start of the next line.
+	fn skip_line(&mut self) {
+		self.position = self.text[self.position..]
+			.find('\n')
+			.map_or(self.text.len(), |newline| self.position + newline + 1);
+	}
+
+	/// Skip whitespace, newlines and comments.
+	fn skip_trivia(&mut self) {
+		loop {
+			match self.peek() {
+				Some(b' ' | b'\t' | b'\r' | b'\n') => self.position += 1,
+				Some(b'#') => self.skip_line(),
+				_ => return,
+			}
+		}
+	}
+
+	/// Skip the string starting at the current position, which is a quote.
+	fn skip_string(&mut self) {
+		let rest = &self.text[self.position..];
+		let quote = &rest[..1];
+		let multiline = rest.starts_with(&quote.repeat(3));
+		let delimiter = if multiline {
+			quote.repeat(3)
+		} else {
+			quote.to_owned()
+		};
+		let mut offset = delimiter.len();
+		while let Some(found) = rest[offset..].find(delimiter.as_str()) {
+			let closing = offset + found;
+			let escaped = quote == "\""
+				&& rest[..closing]
+					.bytes()
+					.rev()
+					.take_while(|byte| *byte == b'\\')
+					.fold(false, |escaped, _| !escaped);
+			offset = closing + delimiter.len();
+			if !escaped {
+				// a multi-line string may end with up to two more quotes
+				while multiline && rest[offset..].starts_with(quote) && offset < rest.len() {
+					offset += 1;
+				}
+				self.position += offset;
+				return;
+			}
+		}
+		self.position = self.text.len();
+	}
+
+	/// Parse a dotted key, unquoting its parts, and skip the spaces after it.
+	fn key_path(&mut self) -> Vec<String> {
+		let mut keys = Vec::new();
+		loop {
+			self.skip_spaces();
+			let start = self.position;
+			if matches!(self.peek(), Some(b'"' | b'\'')) {
+				self.skip_string();
+				// an unterminated string runs to the end of the text
+				let content = self.text.get(start + 1..self.position - 1);
+				keys.push(content.unwrap_or_default().to_owned());
+			} else {
+				while self
+					.peek()
+					.is_some_and(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'_' | b'-'))
+				{
+					self.position += 1;
+				}
+				if self.position == start {
+					return keys;
+				}
+				keys.push(self.text[start..self.position].to_owned());
+			}
+			self.skip_spaces();
+			if self.peek() != Some(b'.') {
+				return keys;
+			}
+			self.position += 1;
+		}
+	}
+
+	/// Parse a `[table]` or `[[array]]` header, stopping after its closing brackets.
+	fn header(&mut self) -> Vec<String> {
+		let array = self.text[self.position..].starts_with("[[");
+		self.position += if array { 2 } else { 1 };
+		let path = self.key_path();
+		let closing = if array { "]]" } else { "]" };
+		if self.text[self.position..].starts_with(closing) {
+			self.position += closing.len();
+		} else {
+			self.skip_line();
+		}
+		path
+	}
+
+	/// Skip a value, stopping after its last character.
+	fn value(&mut self) {
+		let mut depth = 0_usize;
+		let mut end = self.position;
+		while let Some(byte) = self.peek() {
+			match byte {
+				b'"' | b'\'' => {
+					self.skip_string();
+					end = self.position;
+					continue;
+				},
+				b'#' => {
+					// stop at the end of the line, which may end the value
+					self.position = self.text[self.position..]
+						.find('\n')
+						.map_or(self.text.len(), |newline| self.position + newline);
+					continue;
+				},
+				b'\n' if depth == 0 => break,
+				b' ' | b'\t' | b'\r' | b'\n' => {},
+				b'[' | b'{' => {
+					depth += 1;
+					end = self.position + 1;
+				},
+				b']' | b'}' => {
+					depth = depth.saturating_sub(1);
+					end = self.position + 1;
+				},
+				_ => end = self.position + 1,
+			}
+			self.position += 1;
+		}
+		self.position = end;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tests::{TempDir, package};
+
+	const MANIFEST: &str = r#"[package]
+name = "app"
+version = "1.0.0"
+metadata.dependencies.string = "ignored"
+
+[dependencies]
+string = "1.0" # a comment
+inline = { version = "1.0", features = [
+	"extra", # the extra feature
+] }
+helpers = { package = "util", version = "2" }
+"dotted".version = "1.0"
+dotted.features = ["a]"]
+
+[dev-dependencies.table]
+version = "1.0"
+path = '../table'
+
+[target.'cfg(unix)'.build-dependencies]
+unix = """
+1.0"""
+
+[features]
+default = []
+"#;
+
+	fn span(key: &str) -> Option<&'static str> {
+		find_dependency(MANIFEST, key).map(|span| &MANIFEST[span.start..span.end])
+	}
+
+	#[test]
+	fn dependency_spans() {
+		assert_eq!(span("string"), Some(r#"string = "1.0""#));
+		assert_eq!(
+			span("inline"),
+			Some(
+				"inline = { version = \"1.0\", features = [\n\t\"extra\", # the extra feature\n] }"
+			)
+		);
+		assert_eq!(
+			span("helpers"),
+			Some(r#"helpers = { package = "util", version = "2" }"#)
+		);
+		assert_eq!(span("util"), None);
+		assert_eq!(
+			span("dotted"),
+			Some("\"dotted\".version = \"1.0\"\ndotted.features = [\"a]\"]")
+		);
+		assert_eq!(
+			span("table"),
+			Some("[dev-dependencies.table]\nversion = \"1.0\"\npath = '../table'")
+		);
+		assert_eq!(span("unix"), Some("unix = \"\"\"\n1.0\"\"\""));
+		assert_eq!(span("default"), None);
+		assert_eq!(span("name"), None);
+		assert_eq!(find_dependency("[dependencies]\n\"", "lib"), None);
+
+		let interleaved =
+			"[dependencies]\ndotted.version = \"1.0\"\nother = \"2.0\"\ndotted.features = []\n";
+		let span = find_dependency(interleaved, "dotted").unwrap();
+		assert_eq!(
+			&interleaved[span.start..span.end],
+			"dotted.version = \"1.0\""
+		);
+		let span = find_dependency(interleaved, "other").unwrap();
+		assert_eq!(&interleaved[span.start..span.end], "other = \"2.0\"");
+
+		let span = find_dependency(MANIFEST, "helpers").unwrap();
+		assert_eq!((span.line, span.column), (11, 1));
+	}
+
+	#[test]
+	fn dependency_decl_span() {
+		let directory = TempDir::new("manifest-span");
+		let mut package = package("app", "1.0.0");
+		package.manifest_path = directory.write(
+			"wesl.toml",
+			"[dependencies]\n  lib = { path = \"../lib\" }\n",
+		);
+		assert_eq!(
+			package.dependency_decl_span("lib").unwrap(),
+			Some(ManifestSpan {
+				start: 17,
+				end: 42,
+				line: 2,
+				column: 3,
+			})
+		);
+		assert_eq!(package.dependency_decl_span("missing").unwrap(), None);
+
+		drop(directory);
+		assert!(package.dependency_decl_span("lib").unwrap_err().is_io());
+	}
+}
@@ -0,0 +1,295 @@
+//! This module contains [`Metadata::merge`] and the types it uses.
+
+use camino::Utf8PathBuf;
+
+use crate::{Metadata, PackageId, PackageManager, Resolve};
+
+/// Options of [`Metadata::merge_with`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct MergeOptions {
+	/// Merge metadata of different package managers, keeping the package manager of the first.
+	pub allow_mixed_package_managers: bool,
+}
+
+/// What had to be given up when merging metadata, see [`Metadata::merge_with`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct MergeSummary {
+	/// The roots of the merged `resolve` graphs when both had a different one, in which case
+	/// the merged root is `None`.
+	pub root_candidates: Vec<PackageId>,
+}
+
+/// Error returned when merging metadata fails, see [`Metadata::merge`].
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum MergeError {
+	/// The metadata came from different package managers,
+	/// see [`MergeOptions::allow_mixed_package_managers`].
+	#[error("cannot merge metadata of {ours:?} with metadata of {theirs:?}")]
+	MixedPackageManagers {
+		/// The package manager of the metadata merged into.
+		ours: PackageManager,
+		/// The package manager of the metadata being merged.
+		theirs: PackageManager,
+	},
+
+	/// Both metadata contain a package with the same id but different contents.
+	#[error("package `{id}` differs between the merged metadata")]
+	ConflictingPackage {
+		/// The id of the package.
+		id: PackageId,
+	},
+
+	/// The metadata have different target directories.
+	#[error("conflicting target directories `{ours}` and `{theirs}`")]
+	ConflictingTargetDirectory {
+		/// The target directory of the metadata merged into.
+		ours: Utf8PathBuf,
+		/// The target directory of the metadata being merged.
+		theirs: Utf8PathBuf,
+	},
+
+	/// The metadata have different root package directories.
+	#[error("conflicting root package directories `{ours}` and `{theirs}`")]
+	ConflictingRootPackageDirectory {
+		/// The root package directory of the metadata merged into.
+		ours: Utf8PathBuf,
+		/// The root package directory of the metadata being merged.
+		theirs: Utf8PathBuf,
+	},
+}
+
+impl Metadata {
+	/// Merge `other` into this metadata with the default [`MergeOptions`], see
+	/// [`Self::merge_with`].
+	pub fn merge(
+		self,
+		other: Self,
+	) -> Result<Self, MergeError> {
+		self.merge_with(other, &MergeOptions::default())
+			.map(|(merged, _)| merged)
+	}
+
+	/// Merge `other` into this metadata, e.g. the results of running `wesl metadata` for
+	/// each workspace member.
+	///
+	/// Packages are deduplicated by id, and it is an error for the same id to have different
+	/// contents. Workspace members are unioned, and so are the `resolve` nodes, with the
+	/// dependencies of a node present in both deduplicated. A `resolve` root present on only
+	/// one side is kept. If both roots are present and differ, the merged root is `None` and
+	/// the candidates are listed in the returned summary.
+	///
+	/// The target directories, the root package directories and, unless allowed by `options`,
+	/// the package managers must be the same.
+	pub fn merge_with(
+		mut self,
+		other: Self,
+		options: &MergeOptions,
+	) -> Result<(Self, MergeSummary), MergeError> {
+		if self.package_manager != other.package_manager && !options.allow_mixed_package_managers {
+			return Err(MergeError::MixedPackageManagers {
+				ours: self.package_manager,
+				theirs: other.package_manager,
+			});
+		}
+		if self.target_directory != other.target_directory {
+			return Err(MergeError::ConflictingTargetDirectory {
+				ours: self.target_directory,
+				theirs: other.target_directory,
+			});
+		}
+		if self.root_package_directory != other.root_package_directory {
+			return Err(MergeError::ConflictingRootPackageDirectory {
+				ours: self.root_package_directory,
+				theirs: other.root_package_directory,
+			});
+		}
+
+		self.reindex();
+		for package in other.packages {
+			match self.get(&package.id) {
+				Some(existing) if *existing != package => {
+					return Err(MergeError::ConflictingPackage { id: package.id });
+				},
+				Some(_) => {},
+				None => self.packages.push(package),
+			}
+		}
+		self.reindex();
+		for member in other.workspace_members {
+			if !self.workspace_members.contains(&member) {
+				self.workspace_members.push(member);
+			}
+		}
+		self.version = self.version.max(other.version);
+
+		let mut summary = MergeSummary::default();
+		self.resolve = match (self.resolve, other.resolve) {
+			(Some(ours), Some(theirs)) => Some(merge_resolve(ours, theirs, &mut summary)),
+			(ours, theirs) => ours.or(theirs),
+		};
+		Ok((self, summary))
+	}
+}
+
+/// Union the nodes of `theirs` into `ours`, recording disagreeing roots in `summary`.
+fn merge_resolve(
+	mut ours: Resolve,
+	theirs: Resolve,
+	summary: &mut MergeSummary,
+) -> Resolve {
+	for node in theirs.nodes {
+		let Some(existing) = ours
+			.nodes
+			.iter_mut()
+			.find(|existing| existing.id == node.id)
+		else {
+			ours.nodes.push(node);
+			continue;
+		};
+		for dependency in node.dependencies {
+			if !existing.dependencies.contains(&dependency) {
+				existing.dependencies.push(dependency);
+			}
+		}
+		for dependency in node.renamed_dependencies {
+			if !existing.renamed_dependencies.contains(&dependency) {
+				existing.renamed_dependencies.push(dependency);
+			}
+		}
+	}
+	match (ours.root.take(), theirs.root) {
+		(Some(root), Some(other)) if root != other => summary.root_candidates = vec![root, other],
+		(root, other) => ours.root = root.or(other),
+	}
+	ours
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tests::{metadata, node, package};
+
+	fn member(
+		name: &str,
+		root: &str,
+	) -> Metadata {
+		let mut metadata = metadata(vec![package(name, "1.0.0"), package("lib", "1.0.0")]);
+		metadata.workspace_members = vec![PackageId::from(root)];
+		metadata.resolve = Some(Resolve {
+			nodes: vec![node(root, &["lib@1.0.0"]), node("lib@1.0.0", &[])],
+			root: Some(PackageId::from(root)),
+		});
+		metadata
+	}
+
+	#[test]
+	fn merge() {
+		let (merged, summary) = member("app", "app@1.0.0")
+			.merge_with(member("tool", "tool@1.0.0"), &MergeOptions::default())
+			.unwrap();
+		let names: Vec<&str> = merged.iter().map(|package| package.name.as_str()).collect();
+		assert_eq!(names, ["app", "lib", "tool"]);
+		assert_eq!(
+			merged.workspace_members,
+			[PackageId::from("app@1.0.0"), PackageId::from("tool@1.0.0")]
+		);
+		assert!(merged.get(&PackageId::from("tool@1.0.0")).is_some());
+		let resolve = merged.resolve.unwrap();
+		assert_eq!(resolve.nodes.len(), 3);
+		assert_eq!(resolve.root, None);
+		assert_eq!(
+			summary.root_candidates,
+			[PackageId::from("app@1.0.0"), PackageId::from("tool@1.0.0")]
+		);
+	}
+
+	#[test]
+	fn merge_overlapping_edges() {
+		let mut ours = member("app", "app@1.0.0");
+		let mut theirs = member("app", "app@1.0.0");
+		theirs.packages.push(package("util", "1.0.0"));
+		let resolve = theirs.resolve.as_mut().unwrap();
+		resolve.nodes = vec![node("app@1.0.0", &["util@1.0.0", "lib@1.0.0"])];
+		ours.version = 2;
+
+		let (merged, summary) = ours.merge_with(theirs, &MergeOptions::default()).unwrap();
+		assert_eq!(merged.version, 2);
+		assert_eq!(merged.packages.len(), 3);
+		let resolve = merged.resolve.unwrap();
+		assert_eq!(
+			resolve.nodes,
+			[
+				node("app@1.0.0", &["lib@1.0.0", "util@1.0.0"]),
+				node("lib@1.0.0", &[])
+			]
+		);
+		assert_eq!(resolve.root, Some(PackageId::from("app@1.0.0")));
+		assert!(summary.root_candidates.is_empty());
+	}
+
+	#[test]
+	fn merge_one_sided_root() {
+		let mut theirs = member("tool", "tool@1.0.0");
+		theirs.resolve.as_mut().unwrap().root = None;
+		let (merged, summary) = member("app", "app@1.0.0")
+			.merge_with(theirs, &MergeOptions::default())
+			.unwrap();
+		assert_eq!(
+			merged.resolve.unwrap().root,
+			Some(PackageId::from("app@1.0.0"))
+		);
+		assert!(summary.root_candidates.is_empty());
+
+		let mut ours = member("app", "app@1.0.0");
+		ours.resolve.as_mut().unwrap().root = None;
+		let (merged, summary) = ours
+			.merge_with(member("tool", "tool@1.0.0"), &MergeOptions::default())
+			.unwrap();
+		assert_eq!(
+			merged.resolve.unwrap().root,
+			Some(PackageId::from("tool@1.0.0"))
+		);
+		assert!(summary.root_candidates.is_empty());
+	}
+
+	#[test]
+	fn merge_conflicts() {
+		let mut theirs = member("tool", "tool@1.0.0");
+		theirs.packages.last_mut().unwrap().description = Some("changed".to_owned());
+		assert_eq!(
+			member("app", "app@1.0.0").merge(theirs).unwrap_err(),
+			MergeError::ConflictingPackage {
+				id: PackageId::from("lib@1.0.0")
+			}
+		);
+
+		let mut theirs = member("tool", "tool@1.0.0");
+		theirs.target_directory = "/elsewhere".into();
+		assert!(matches!(
+			member("app", "app@1.0.0").merge(theirs).unwrap_err(),
+			MergeError::ConflictingTargetDirectory { .. }
+		));
+
+		let mut theirs = member("tool", "tool@1.0.0");
+		theirs.package_manager = PackageManager::Npm;
+		assert_eq!(
+			member("app", "app@1.0.0")
+				.merge(theirs.clone())
+				.unwrap_err(),
+			MergeError::MixedPackageManagers {
+				ours: PackageManager::Cargo,
+				theirs: PackageManager::Npm,
+			}
+		);
+		let options = MergeOptions {
+			allow_mixed_package_managers: true,
+		};
+		let (merged, _) = member("app", "app@1.0.0")
+			.merge_with(theirs, &options)
+			.unwrap();
+		assert_eq!(merged.package_manager, PackageManager::Cargo);
+	}
+}
@@ -0,0 +1,128 @@
+//! This module contains [`MetadataCommand::exec_partial`] and the types it returns.
+
+use crate::{Error, Metadata, MetadataCommand, Result};
+
+/// Metadata parsed from the output of a failed `wesl metadata`, see
+/// [`MetadataCommand::exec_partial`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PartialMetadata {
+	/// The metadata that was printed, which may be missing packages.
+	pub metadata: Metadata,
+
+	/// The stderr of `wesl metadata`, explaining the failure.
+	pub stderr: String,
+
+	/// The exit code of `wesl metadata`, if it was not killed by a signal.
+	pub exit_code: Option<i32>,
+}
+
+/// The result of [`MetadataCommand::exec_partial`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PartialResult {
+	/// `wesl metadata` succeeded.
+	Complete(Metadata),
+	/// `wesl metadata` failed, but printed metadata.
+	Partial(PartialMetadata),
+}
+
+impl PartialResult {
+	/// Whether `wesl metadata` failed.
+	#[must_use]
+	pub const fn is_partial(&self) -> bool {
+		matches!(self, Self::Partial(_))
+	}
+
+	/// The metadata, complete or not.
+	#[must_use]
+	pub fn into_metadata(self) -> Metadata {
+		match self {
+			Self::Complete(metadata) | Self::Partial(PartialMetadata { metadata, .. }) => metadata,
+		}
+	}
+}
+
+impl MetadataCommand {
+	/// Runs configured `wesl metadata` like [`Self::exec`], but with [`Self::allow_partial`]
+	/// also parses the JSON printed by a failed run.
+	///
+	/// If the failed run printed no JSON that parses, or partial results are not allowed,
+	/// the [`Error::WeslMetadata`] of [`Self::exec`] is returned unchanged.
+	pub fn exec_partial(&self) -> Result<PartialResult> {
+		match self.exec() {
+			Ok(metadata) => Ok(PartialResult::Complete(metadata)),
+			Err(Error::WeslMetadata {
+				exit_code,
+				stderr,
+				stdout,
+			}) => {
+				let parsed = self
+					.allow_partial
+					.then(|| self.parse_output(&stdout).ok())
+					.flatten();
+				match parsed {
+					Some(metadata) => Ok(PartialResult::Partial(PartialMetadata {
+						metadata,
+						stderr,
+						exit_code,
+					})),
+					None => Err(Error::WeslMetadata {
+						exit_code,
+						stderr,
+						stdout,
+					}),
+				}
+			},
+			Err(error) => Err(error),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	#[cfg(unix)]
+	use super::*;
+	#[cfg(unix)]
+	use crate::tests::TempDir;
+
+	#[cfg(unix)]
+	#[test]
+	fn exec_partial() {
+		const JSON: &str = r#"{"package_manager": "Cargo", "packages": [], "target_directory": "/target", "version": 1, "root_package_directory": "/"}"#;
+		let directory = TempDir::new("exec-partial");
+		let mut command = MetadataCommand::new();
+		command.allow_partial(true);
+		assert!(command.is_partial_allowed());
+
+		command.wesl_path(directory.shim("partial-complete", &format!("echo '{JSON}'")));
+		let result = command.exec_partial().unwrap();
+		assert!(!result.is_partial());
+		assert_eq!(result.into_metadata().target_directory, "/target");
+
+		command.wesl_path(directory.shim(
+			"partial-failed",
+			&format!("echo '{JSON}'\necho 'broken manifest' >&2\nexit 3"),
+		));
+		let PartialResult::Partial(partial) = command.exec_partial().unwrap() else {
+			panic!("expected partial metadata");
+		};
+		assert_eq!(partial.metadata.target_directory, "/target");
+		assert_eq!(partial.stderr, "broken manifest\n");
+		assert_eq!(partial.exit_code, Some(3));
+		assert!(matches!(
+			command.clone().allow_partial(false).exec_partial(),
+			Err(Error::WeslMetadata { stdout, .. }) if stdout.contains("/target")
+		));
+		assert!(matches!(command.exec(), Err(Error::WeslMetadata { .. })));
+
+		command.wesl_path(directory.shim(
+			"partial-nothing",
+			"echo 'Compiling'\necho 'broken manifest' >&2\nexit 3",
+		));
+		assert!(matches!(
+			command.exec_partial(),
+			Err(Error::WeslMetadata { exit_code: Some(3), stderr, stdout })
+				if stderr == "broken manifest\n" && stdout == "Compiling\n"
+		));
+	}
+}
@@ -0,0 +1,343 @@
+//! This module contains [`Metadata::rebase_paths`] and the other path rewriting helpers.
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use crate::Metadata;
+
+/// What happened to the paths of a [`Metadata`] when rewriting them,
+/// see [`Metadata::rebase_paths`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct RebaseSummary {
+	/// The number of paths that were rewritten.
+	pub rewritten: usize,
+
+	/// The paths that were left alone because they did not start with the prefix.
+	pub untouched: Vec<Utf8PathBuf>,
+}
+
+impl RebaseSummary {
+	/// Rewrite `path` with `rewrite`, or record it as untouched if `rewrite` returns `None`.
+	fn apply<Rewrite: FnOnce(&str) -> Option<Utf8PathBuf>>(
+		&mut self,
+		path: &mut Utf8PathBuf,
+		rewrite: Rewrite,
+	) {
+		if let Some(rewritten) = rewrite(path.as_str()) {
+			*path = rewritten;
+			self.rewritten += 1;
+		} else {
+			self.untouched.push(path.clone());
+		}
+	}
+}
+
+/// The kind of a path, which decides what it is relative to.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PathKind {
+	/// Relative to the root package directory when not absolute.
+	Root,
+	/// Relative to the directory of the package manifest, like `license-file` and `readme`.
+	Manifest,
+}
+
+const fn is_separator(character: char) -> bool {
+	matches!(character, '/' | '\\')
+}
+
+/// The rest of `path` after `prefix`, compared component by component with either separator.
+pub(crate) fn strip_prefix<'path>(
+	path: &'path str,
+	prefix: &str,
+) -> Option<&'path str> {
+	let prefix = prefix.trim_end_matches(is_separator);
+	let rest = path.strip_prefix(prefix)?;
+	if prefix.is_empty() {
+		// an empty prefix only matches relative paths
+		return (!is_absolute(path)).then_some(path);
+	}
+	(rest.is_empty() || rest.starts_with(is_separator))
+		.then(|| rest.trim_start_matches(is_separator))
+}
+
+/// `rest` under `prefix`, using the separator of `prefix` throughout.
+fn join(
+	prefix: &str,
+	rest: &str,
+) -> Utf8PathBuf {
+	let prefix = prefix.trim_end_matches(is_separator);
+	if prefix.is_empty() || rest.is_empty() {
+		let path = if prefix.is_empty() { rest } else { prefix };
+		return path.into();
+	}
+	let separator = if prefix.contains('\\') && !prefix.contains('/') {
+		'\\'
+	} else {
+		'/'
+	};
+	let rest: String = rest
+		.chars()
+		.map(|character| {
+			if is_separator(character) {
+				separator
+			} else {
+				character
+			}
+		})
+		.collect();
+	format!("{prefix}{separator}{rest}").into()
+}
+
+/// Whether `path` is absolute on any platform, e.g. `/app` or `C:\app`.
+fn is_absolute(path: &str) -> bool {
+	let mut characters = path.chars();
+	match (characters.next(), characters.next()) {
+		(Some(first), _) if is_separator(first) => true,
+		(Some(drive), Some(':')) => drive.is_ascii_alphabetic(),
+		_ => false,
+	}
+}
+
+impl Metadata {
+	/// Call `visit` with every path of this metadata and its kind.
+	///
+	/// The last argument of `visit` is the directory of the package manifest, as it was
+	/// before any path of that package was visited.
+	fn visit_paths<Visit: FnMut(&mut Utf8PathBuf, PathKind, &Utf8Path)>(
+		&mut self,
+		mut visit: Visit,
+	) {
+		let root = self.root_package_directory.clone();
+		visit(&mut self.target_directory, PathKind::Root, &root);
+		for package in &mut self.packages {
+			let directory = package.manifest_dir().to_owned();
+			visit(&mut package.manifest_path, PathKind::Root, &directory);
+			for path in [&mut package.license_file, &mut package.readme]
+				.into_iter()
+				.flatten()
+			{
+				visit(path, PathKind::Manifest, &directory);
+			}
+			for target in &mut package.targets {
+				visit(&mut target.src_path, PathKind::Root, &directory);
+			}
+			for path in package
+				.dependencies
+				.iter_mut()
+				.filter_map(|dependency| dependency.path.as_mut())
+			{
+				visit(path, PathKind::Root, &directory);
+			}
+		}
+		visit(&mut self.root_package_directory, PathKind::Root, &root);
+	}
+
+	/// Replace `old_prefix` with `new_prefix` in every path, e.g. to move metadata generated on
+	/// another machine.
+	///
+	/// This covers `target_directory`, `root_package_directory`, and the `manifest_path`,
+	/// `license_file`, `readme`, target `src_path` and dependency `path` of every package.
+	/// Prefixes match whole components, and `/` and `\` are both separators, so Windows paths
+	/// can be rebased on any platform. Paths that do not start with `old_prefix` are left
+	/// alone and listed in the returned summary.
+	pub fn rebase_paths(
+		&mut self,
+		old_prefix: &Utf8Path,
+		new_prefix: &Utf8Path,
+	) -> RebaseSummary {
+		let mut summary = RebaseSummary::default();
+		self.visit_paths(|path, _, _| {
+			summary.apply(path, |path| {
+				strip_prefix(path, old_prefix.as_str()).map(|rest| join(new_prefix.as_str(), rest))
+			});
+		});
+		summary
+	}
+
+	/// Replace every `\` separator with `/`, so that paths reported by `wesl` on Windows,
+	/// possibly with mixed separators, compare equal to each other.
+	///
+	/// This covers the same paths as [`Self::rebase_paths`], including `manifest_path`,
+	/// target `src_path`, `target_directory` and `root_package_directory`. It only rewrites
+	/// the strings and does not access the file system. Note that on other platforms, `\` is
+	/// a valid character in a file name.
+	pub fn normalize_paths(&mut self) {
+		self.visit_paths(|path, _, _| {
+			if path.as_str().contains('\\') {
+				*path = path.as_str().replace('\\', "/").into();
+			}
+		});
+	}
+
+	/// Make every path relative, undone by [`Self::absolutize`].
+	///
+	/// Paths are made relative to `root_package_directory`, which itself becomes empty, except
+	/// for `license_file` and `readme`, which are made relative to the directory of their
+	/// manifest as in `wesl.toml`. Paths outside of those directories are left alone and
+	/// listed in the returned summary.
+	pub fn relativize(&mut self) -> RebaseSummary {
+		let mut summary = RebaseSummary::default();
+		let root = self.root_package_directory.clone();
+		self.visit_paths(|path, kind, directory| {
+			let base = if kind == PathKind::Manifest {
+				directory
+			} else {
+				&root
+			};
+			summary.apply(path, |path| {
+				if kind == PathKind::Manifest && !is_absolute(path) {
+					Some(path.into())
+				} else {
+					strip_prefix(path, base.as_str()).map(Utf8PathBuf::from)
+				}
+			});
+		});
+		summary
+	}
+
+	/// Make every relative path absolute by joining it to `root`, undoing [`Self::relativize`].
+	///
+	/// `root_package_directory` becomes `root` if it is empty. `license_file` and `readme`
+	/// stay relative to the directory of their manifest, as in `wesl.toml`.
+	pub fn absolutize(
+		&mut self,
+		root: &Utf8Path,
+	) -> RebaseSummary {
+		let mut summary = RebaseSummary::default();
+		self.visit_paths(|path, kind, _| {
+			if kind == PathKind::Root && !is_absolute(path.as_str()) {
+				summary.apply(path, |path| Some(join(root.as_str(), path)));
+			}
+		});
+		summary
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tests::{metadata, package};
+	use crate::{Dependency, DependencyKind};
+
+	fn fixture(root: &str) -> Metadata {
+		let mut app = package("app", "1.0.0");
+		app.manifest_path = join(root, "app/wesl.toml");
+		app.license_file = Some("LICENSE".into());
+		app.readme = Some(join(root, "app/docs/README.md"));
+		app.dependencies = vec![Dependency {
+			name: "lib".to_owned(),
+			rename: None,
+			path: Some(join(root, "lib")),
+			git: None,
+			kind: DependencyKind::Normal,
+			optional: false,
+			uses_default_features: true,
+			features: Vec::new(),
+		}];
+		let mut registry = package("util", "1.0.0");
+		registry.manifest_path = "/registry/util/wesl.toml".into();
+		let mut metadata = metadata(vec![app, registry]);
+		metadata.target_directory = join(root, "target");
+		metadata.root_package_directory = root.into();
+		metadata
+	}
+
+	fn paths(metadata: &Metadata) -> Vec<String> {
+		let mut metadata = metadata.clone();
+		let mut paths = Vec::new();
+		metadata.visit_paths(|path, _, _| paths.push(path.to_string()));
+		paths
+	}
+
+	#[test]
+	fn rebase_windows_paths() {
+		let mut metadata = fixture(r"C:\build\app");
+		let summary = metadata.rebase_paths(r"C:\build".into(), "/home/dev".into());
+		assert_eq!(summary.rewritten, 5);
+		assert_eq!(
+			summary.untouched,
+			[
+				Utf8PathBuf::from("LICENSE"),
+				Utf8PathBuf::from("/registry/util/wesl.toml")
+			]
+		);
+		assert_eq!(
+			paths(&metadata),
+			[
+				"/home/dev/app/target",
+				"/home/dev/app/app/wesl.toml",
+				"LICENSE",
+				"/home/dev/app/app/docs/README.md",
+				"/home/dev/app/lib",
+				"/registry/util/wesl.toml",
+				"/home/dev/app",
+			]
+		);
+	}
+
+	#[test]
+	fn rebase_exact_prefix() {
+		let mut package = package("app", "1.0.0");
+		package.manifest_path = "/build/app/wesl.toml".into();
+		let mut metadata = metadata(vec![package]);
+		metadata.target_directory = "/build/app/wesl.toml-target".into();
+		metadata.root_package_directory = "/build/app".into();
+
+		let summary = metadata.rebase_paths("/build/app/wesl.toml".into(), "/dev/wesl.toml".into());
+		assert_eq!(summary.rewritten, 1);
+		assert_eq!(
+			metadata.packages.first().unwrap().manifest_path,
+			"/dev/wesl.toml"
+		);
+		assert_eq!(metadata.target_directory, "/build/app/wesl.toml-target");
+	}
+
+	#[test]
+	fn normalize_paths() {
+		let mut app = package("app", "1.0.0");
+		app.manifest_path = r"C:\build\app\wesl.toml".into();
+		let mut metadata = metadata(vec![app]);
+		metadata.root_package_directory = r"C:\build/app".into();
+		metadata.target_directory = r"C:\build\app\target".into();
+		assert_eq!(metadata.root_package(), None);
+
+		metadata.normalize_paths();
+		assert_eq!(metadata.root_package().unwrap().name, "app");
+		assert_eq!(
+			paths(&metadata),
+			[
+				"C:/build/app/target",
+				"C:/build/app/wesl.toml",
+				"C:/build/app"
+			]
+		);
+	}
+
+	#[test]
+	fn relativize_and_absolutize() {
+		let original = fixture("/build/app");
+		let mut metadata = original.clone();
+		let summary = metadata.relativize();
+		assert_eq!(
+			summary.untouched,
+			[Utf8PathBuf::from("/registry/util/wesl.toml")]
+		);
+		assert_eq!(
+			paths(&metadata),
+			[
+				"target",
+				"app/wesl.toml",
+				"LICENSE",
+				"docs/README.md",
+				"lib",
+				"/registry/util/wesl.toml",
+				"",
+			]
+		);
+
+		metadata.absolutize("/build/app".into());
+		let mut expected = original;
+		expected.packages.first_mut().unwrap().readme = Some("docs/README.md".into());
+		assert_eq!(metadata, expected);
+	}
+}
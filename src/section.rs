@@ -0,0 +1,187 @@
+//! This module contains [`Package::metadata_section`] and the error it returns.
+
+use std::collections::BTreeMap;
+
+use serde::de::DeserializeOwned;
+
+use crate::{Metadata, Package, PackageId};
+
+/// Error returned when a section of `package.metadata` does not have the expected shape,
+/// see [`Package::metadata_section`].
+#[derive(Debug, thiserror::Error)]
+#[error("invalid `{key}` section in the metadata of package `{package}`: {source}")]
+#[non_exhaustive]
+pub struct MetadataSectionError {
+	/// The name of the package.
+	pub package: String,
+
+	/// The key of the section.
+	pub key: String,
+
+	/// The deserialization error, describing the unexpected value and the expected type.
+	///
+	/// It does not include where in the section the error is, e.g. `output.formats[1]`,
+	/// and its line and column are always 0, as the section is not parsed from text.
+	pub source: serde_json::Error,
+}
+
+impl Package {
+	/// Deserialize the `key` section of the free form `metadata` of this package, e.g. the
+	/// settings of a tool.
+	///
+	/// Returns `None` if `metadata` is not an object or does not contain `key`.
+	///
+	/// ```
+	/// # use serde::Deserialize;
+	/// # use wesl_metadata::Package;
+	/// #[derive(Deserialize)]
+	/// struct Bundler {
+	///     minify: bool,
+	/// }
+	///
+	/// # fn example(package: &Package) -> Result<(), wesl_metadata::MetadataSectionError> {
+	/// if let Some(bundler) = package.metadata_section::<Bundler>("bundler")? {
+	///     println!("minify {}: {}", package.name, bundler.minify);
+	/// }
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn metadata_section<Section: DeserializeOwned>(
+		&self,
+		key: &str,
+	) -> Result<Option<Section>, MetadataSectionError> {
+		let Some(value) = self.metadata.get(key) else {
+			return Ok(None);
+		};
+		Section::deserialize(value)
+			.map(Some)
+			.map_err(|source| MetadataSectionError {
+				package: self.name.clone(),
+				key: key.to_owned(),
+				source,
+			})
+	}
+}
+
+impl Metadata {
+	/// Deserialize the `key` section of the metadata of every package that has one,
+	/// see [`Package::metadata_section`].
+	///
+	/// Packages without the section are skipped, and a package whose section fails to
+	/// deserialize does not stop the others.
+	#[must_use]
+	pub fn collect_metadata_sections<Section: DeserializeOwned>(
+		&self,
+		key: &str,
+	) -> BTreeMap<&PackageId, Result<Section, MetadataSectionError>> {
+		self.packages
+			.iter()
+			.filter_map(|package| {
+				package
+					.metadata_section(key)
+					.transpose()
+					.map(|section| (&package.id, section))
+			})
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde::Deserialize;
+	use serde_json::json;
+
+	use super::*;
+	use crate::tests::{metadata, package};
+
+	#[derive(Debug, Deserialize, PartialEq, Eq)]
+	struct Bundler {
+		minify: bool,
+		output: Output,
+	}
+
+	#[derive(Debug, Deserialize, PartialEq, Eq)]
+	struct Output {
+		directory: String,
+		formats: Vec<String>,
+	}
+
+	fn with_metadata(
+		name: &str,
+		metadata: serde_json::Value,
+	) -> Package {
+		let mut package = package(name, "1.0.0");
+		package.metadata = metadata;
+		package
+	}
+
+	#[test]
+	fn metadata_section() {
+		let app = with_metadata(
+			"app",
+			json!({
+				"bundler": {
+					"minify": true,
+					"output": {"directory": "dist", "formats": ["wgsl"]},
+				},
+				"other-tool": 3,
+			}),
+		);
+		assert_eq!(
+			app.metadata_section::<Bundler>("bundler").unwrap(),
+			Some(Bundler {
+				minify: true,
+				output: Output {
+					directory: "dist".to_owned(),
+					formats: vec!["wgsl".to_owned()],
+				},
+			})
+		);
+		assert!(
+			app.metadata_section::<Bundler>("missing")
+				.unwrap()
+				.is_none()
+		);
+		assert!(
+			package("null", "1.0.0")
+				.metadata_section::<Bundler>("bundler")
+				.unwrap()
+				.is_none()
+		);
+
+		let error = app.metadata_section::<Bundler>("other-tool").unwrap_err();
+		assert_eq!(error.package, "app");
+		assert_eq!(error.key, "other-tool");
+		assert!(
+			error
+				.to_string()
+				.starts_with("invalid `other-tool` section in the metadata of package `app`: ")
+		);
+	}
+
+	#[test]
+	fn collect_metadata_sections() {
+		let section = json!({"minify": false, "output": {"directory": "out", "formats": []}});
+		let metadata = metadata(vec![
+			with_metadata("app", json!({ "bundler": section })),
+			with_metadata("lib", json!({ "bundler": section })),
+			with_metadata("broken", json!({"bundler": {"minify": "yes"}})),
+			with_metadata("plain", json!({"other-tool": {}})),
+			package("null", "1.0.0"),
+		]);
+		let sections = metadata.collect_metadata_sections::<Bundler>("bundler");
+		let ids: Vec<&str> = sections.keys().map(|id| id.as_str()).collect();
+		assert_eq!(ids, ["app@1.0.0", "broken@1.0.0", "lib@1.0.0"]);
+		let (failed, parsed): (Vec<_>, Vec<_>) =
+			sections.values().partition(|section| section.is_err());
+		assert_eq!(parsed.len(), 2);
+		assert_eq!(failed.len(), 1);
+		assert!(
+			sections[&PackageId::from("broken@1.0.0")]
+				.as_ref()
+				.unwrap_err()
+				.source
+				.is_data()
+		);
+	}
+}
@@ -0,0 +1,210 @@
+//! This module contains [`Package::source_files`] and the glob matching it uses.
+
+use std::collections::BTreeSet;
+use std::fs;
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use crate::{Error, Metadata, Package, Result};
+
+/// The extensions of shader source files.
+const SOURCE_EXTENSIONS: [&str; 2] = ["wesl", "wgsl"];
+
+impl Package {
+	/// List the `.wesl` and `.wgsl` files in the directory of this package, sorted.
+	///
+	/// Directories are walked recursively, following symbolic links but visiting each
+	/// directory once. The `target` directory next to the manifest and nested packages,
+	/// which have their own `wesl.toml`, are skipped. See [`Metadata::source_files`] to skip
+	/// a target directory in another place.
+	///
+	/// Paths relative to the package directory are matched against the `exclude` and
+	/// `include` globs: excluded files and directories are skipped, and if `include` is not
+	/// empty, only files matching one of its globs are listed. A glob without a `/` matches a
+	/// name at any depth. `*` and `?` do not match `/`, and `**` matches any number of
+	/// directories.
+	pub fn source_files(&self) -> Result<Vec<Utf8PathBuf>> {
+		self.walk(&self.manifest_dir().join("target"))
+	}
+
+	fn walk(
+		&self,
+		target_directory: &Utf8Path,
+	) -> Result<Vec<Utf8PathBuf>> {
+		let root = self.manifest_dir();
+		let target_directory = fs::canonicalize(target_directory).ok();
+		let mut files = Vec::new();
+		let mut visited = BTreeSet::new();
+		let mut stack = vec![root.to_owned()];
+		while let Some(directory) = stack.pop() {
+			let canonical = fs::canonicalize(&directory)?;
+			if target_directory.as_ref() == Some(&canonical) || !visited.insert(canonical) {
+				continue;
+			}
+			for entry in fs::read_dir(&directory)? {
+				let path = Utf8PathBuf::try_from(entry?.path())
+					.map_err(|error| Error::Io(error.into_io_error()))?;
+				let relative = path.strip_prefix(root).map_or_else(
+					|_| path.to_string(),
+					|relative| relative.as_str().replace('\\', "/"),
+				);
+				if self
+					.exclude
+					.iter()
+					.any(|glob| glob_matches(glob, &relative))
+				{
+					continue;
+				}
+				if path.is_dir() {
+					if !path.join("wesl.toml").is_file() {
+						stack.push(path);
+					}
+				} else if path
+					.extension()
+					.is_some_and(|extension| SOURCE_EXTENSIONS.contains(&extension))
+					&& (self.include.is_empty()
+						|| self
+							.include
+							.iter()
+							.any(|glob| glob_matches(glob, &relative)))
+				{
+					files.push(path);
+				}
+			}
+		}
+		files.sort();
+		Ok(files)
+	}
+}
+
+impl Metadata {
+	/// List the source files of `package` like [`Package::source_files`], skipping
+	/// `target_directory` wherever it is.
+	pub fn source_files(
+		&self,
+		package: &Package,
+	) -> Result<Vec<Utf8PathBuf>> {
+		package.walk(&self.target_directory)
+	}
+}
+
+/// Whether the `/` separated `relative` path matches `glob`.
+///
+/// A glob without a `/` is matched against the last component only.
+fn glob_matches(
+	glob: &str,
+	relative: &str,
+) -> bool {
+	let glob = glob.trim_end_matches('/');
+	if glob.contains('/') {
+		matches(glob.trim_start_matches('/'), relative)
+	} else {
+		relative
+			.rsplit('/')
+			.next()
+			.is_some_and(|name| matches(glob, name))
+	}
+}
+
+fn matches(
+	glob: &str,
+	text: &str,
+) -> bool {
+	if glob == "**" {
+		return true;
+	}
+	if let Some(rest) = glob.strip_prefix("**/") {
+		return matches(rest, text)
+			|| text
+				.split_once('/')
+				.is_some_and(|(_, after)| matches(glob, after));
+	}
+	let mut text_characters = text.chars();
+	let next = text_characters.next();
+	if let Some(rest) = glob.strip_prefix('*') {
+		return matches(rest, text)
+			|| next.is_some_and(|character| {
+				character != '/' && matches(glob, text_characters.as_str())
+			});
+	}
+	let mut glob_characters = glob.chars();
+	match (glob_characters.next(), next) {
+		(None, None) => true,
+		(Some('?'), Some(character)) => {
+			character != '/' && matches(glob_characters.as_str(), text_characters.as_str())
+		},
+		(Some(expected), Some(character)) => {
+			expected == character && matches(glob_characters.as_str(), text_characters.as_str())
+		},
+		(None, Some(_)) | (Some(_), None) => false,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tests::{TempDir, metadata, package};
+
+	#[test]
+	fn globs() {
+		assert!(glob_matches("*.gen.wgsl", "shaders/lights.gen.wgsl"));
+		assert!(!glob_matches("*.gen.wgsl", "shaders/lights.wgsl"));
+		assert!(glob_matches("shaders/*.wgsl", "shaders/lights.wgsl"));
+		assert!(!glob_matches("shaders/*.wgsl", "shaders/deep/lights.wgsl"));
+		assert!(glob_matches("shaders/**/*.wgsl", "shaders/lights.wgsl"));
+		assert!(glob_matches(
+			"/shaders/**/*.wgsl",
+			"shaders/deep/lights.wgsl"
+		));
+		assert!(glob_matches("generated/", "shaders/generated"));
+		assert!(glob_matches("light?.wesl", "a/light1.wesl"));
+	}
+
+	#[test]
+	fn source_files() {
+		let directory = TempDir::new("sources");
+		let root = directory.path();
+		for file in [
+			"wesl.toml",
+			"main.wesl",
+			"README.md",
+			"shaders/lights.wgsl",
+			"shaders/deep/shadows.wesl",
+			"shaders/lights.gen.wgsl",
+			"generated/skip.wgsl",
+			"target/out.wgsl",
+			"nested/wesl.toml",
+			"nested/other.wesl",
+		] {
+			directory.write(file, "");
+		}
+		#[cfg(unix)]
+		std::os::unix::fs::symlink(root.join("shaders"), root.join("shaders/deep/loop")).unwrap();
+
+		let mut package = package("app", "1.0.0");
+		package.manifest_path = root.join("wesl.toml");
+		package.exclude = vec!["*.gen.wgsl".to_owned(), "generated".to_owned()];
+		let relative = |files: Vec<Utf8PathBuf>| -> Vec<String> {
+			files
+				.iter()
+				.map(|file| file.strip_prefix(root).unwrap().to_string())
+				.collect()
+		};
+		assert_eq!(
+			relative(package.source_files().unwrap()),
+			[
+				"main.wesl",
+				"shaders/deep/shadows.wesl",
+				"shaders/lights.wgsl"
+			]
+		);
+
+		package.include = vec!["shaders/**".to_owned()];
+		let mut metadata = metadata(vec![package.clone()]);
+		metadata.target_directory = root.join("shaders/deep");
+		assert_eq!(
+			relative(metadata.source_files(&package).unwrap()),
+			["shaders/lights.wgsl"]
+		);
+	}
+}
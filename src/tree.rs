@@ -0,0 +1,272 @@
+//! This module contains [`Metadata::render_tree`] and its options.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::graph::edges;
+use crate::{Metadata, Package, PackageId};
+
+/// Characters used to draw the branches of a rendered tree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum PrefixStyle {
+	/// Box-drawing characters, like `cargo tree`.
+	#[default]
+	Unicode,
+	/// Plain ASCII characters.
+	Ascii,
+}
+
+impl PrefixStyle {
+	/// The prefixes for a child which is not the last, a last child,
+	/// the continuation below a child which is not the last, and the continuation below a last child.
+	const fn prefixes(self) -> [&'static str; 4] {
+		match self {
+			Self::Unicode => [
+				"\u{251c}\u{2500}\u{2500} ",
+				"\u{2514}\u{2500}\u{2500} ",
+				"\u{2502}   ",
+				"    ",
+			],
+			Self::Ascii => ["|-- ", "`-- ", "|   ", "    "],
+		}
+	}
+}
+
+/// Options for [`Metadata::render_tree`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TreeOptions {
+	/// Package to start from. Defaults to the root of the resolve graph.
+	pub root: Option<PackageId>,
+
+	/// Maximum depth of the tree. The tree is not limited if `None`.
+	pub max_depth: Option<usize>,
+
+	/// Characters used to draw the branches.
+	pub prefix_style: PrefixStyle,
+
+	/// Show the packages depending on `root` instead of its dependencies.
+	pub invert: bool,
+}
+
+impl Metadata {
+	/// Render the dependency tree as text, like `cargo tree`.
+	///
+	/// Each package is shown as `name vX.Y.Z`. A package whose dependencies were already shown
+	/// is marked with `(*)` instead of being expanded again, and an edge leading back to a package
+	/// on the current path is marked with `(cycle)`.
+	///
+	/// Returns an empty string if there is no resolve graph or no root to start from.
+	#[must_use]
+	pub fn render_tree(
+		&self,
+		options: &TreeOptions,
+	) -> String {
+		let Some(resolve) = &self.resolve else {
+			return String::new();
+		};
+		let Some(root) = options.root.as_ref().or(resolve.root.as_ref()) else {
+			return String::new();
+		};
+
+		let mut children: BTreeMap<&PackageId, Vec<&PackageId>> = BTreeMap::new();
+		for node in &resolve.nodes {
+			for (dependency, _) in edges(node) {
+				if options.invert {
+					children.entry(dependency).or_default().push(&node.id);
+				} else {
+					children.entry(&node.id).or_default().push(dependency);
+				}
+			}
+		}
+
+		Tree {
+			packages: self.index_by_id(),
+			children,
+			root,
+			options,
+		}
+		.to_string()
+	}
+}
+
+struct Tree<'meta> {
+	packages: BTreeMap<&'meta PackageId, &'meta Package>,
+	children: BTreeMap<&'meta PackageId, Vec<&'meta PackageId>>,
+	root: &'meta PackageId,
+	options: &'meta TreeOptions,
+}
+
+impl<'meta> Tree<'meta> {
+	fn write_package(
+		&self,
+		formatter: &mut fmt::Formatter<'_>,
+		prefix: &mut String,
+		path: &mut Vec<&'meta PackageId>,
+		expanded: &mut BTreeSet<&'meta PackageId>,
+		id: &'meta PackageId,
+	) -> fmt::Result {
+		match self.packages.get(id) {
+			Some(package) => write!(formatter, "{} v{}", package.name, package.version)?,
+			None => write!(formatter, "{id}")?,
+		}
+		let children = self.children.get(id).map_or(&[][..], Vec::as_slice);
+		if path.contains(&id) {
+			return writeln!(formatter, " (cycle)");
+		}
+		if !children.is_empty() && expanded.contains(id) {
+			return writeln!(formatter, " (*)");
+		}
+		writeln!(formatter)?;
+		if self
+			.options
+			.max_depth
+			.is_some_and(|max_depth| path.len() >= max_depth)
+		{
+			return Ok(());
+		}
+
+		let [branch, last_branch, continuation, last_continuation] =
+			self.options.prefix_style.prefixes();
+		path.push(id);
+		for (index, child) in children.iter().enumerate() {
+			let last = index + 1 == children.len();
+			formatter.write_str(prefix)?;
+			formatter.write_str(if last { last_branch } else { branch })?;
+			let length = prefix.len();
+			prefix.push_str(if last {
+				last_continuation
+			} else {
+				continuation
+			});
+			self.write_package(formatter, prefix, path, expanded, child)?;
+			prefix.truncate(length);
+		}
+		path.pop();
+		// a package cut off by `max_depth` is expanded where it appears again higher up
+		expanded.insert(id);
+		Ok(())
+	}
+}
+
+impl fmt::Display for Tree<'_> {
+	fn fmt(
+		&self,
+		formatter: &mut fmt::Formatter<'_>,
+	) -> fmt::Result {
+		self.write_package(
+			formatter,
+			&mut String::new(),
+			&mut Vec::new(),
+			&mut BTreeSet::new(),
+			self.root,
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Resolve;
+	use crate::tests::{metadata, node, package};
+
+	fn fixture() -> Metadata {
+		let mut metadata = metadata(vec![
+			package("app", "1.0.0"),
+			package("lib", "1.0.0"),
+			package("util", "1.0.0"),
+			package("ping", "1.0.0"),
+			package("pong", "1.0.0"),
+		]);
+		metadata.resolve = Some(Resolve {
+			nodes: vec![
+				node("app@1.0.0", &["lib@1.0.0", "util@1.0.0", "ping@1.0.0"]),
+				node("lib@1.0.0", &["util@1.0.0"]),
+				node("util@1.0.0", &["pong@1.0.0"]),
+				node("ping@1.0.0", &["pong@1.0.0"]),
+				node("pong@1.0.0", &["ping@1.0.0"]),
+			],
+			root: Some(PackageId {
+				repr: "app@1.0.0".to_owned(),
+			}),
+		});
+		metadata
+	}
+
+	#[test]
+	fn render_tree() {
+		assert_eq!(
+			fixture().render_tree(&TreeOptions::default()),
+			"\
+app v1.0.0
+\u{251c}\u{2500}\u{2500} lib v1.0.0
+\u{2502}   \u{2514}\u{2500}\u{2500} util v1.0.0
+\u{2502}       \u{2514}\u{2500}\u{2500} pong v1.0.0
+\u{2502}           \u{2514}\u{2500}\u{2500} ping v1.0.0
+\u{2502}               \u{2514}\u{2500}\u{2500} pong v1.0.0 (cycle)
+\u{251c}\u{2500}\u{2500} util v1.0.0 (*)
+\u{2514}\u{2500}\u{2500} ping v1.0.0 (*)
+"
+		);
+	}
+
+	#[test]
+	fn render_tree_max_depth() {
+		let options = TreeOptions {
+			max_depth: Some(1),
+			prefix_style: PrefixStyle::Ascii,
+			..TreeOptions::default()
+		};
+		assert_eq!(
+			fixture().render_tree(&options),
+			"\
+app v1.0.0
+|-- lib v1.0.0
+|-- util v1.0.0
+`-- ping v1.0.0
+"
+		);
+	}
+
+	#[test]
+	fn render_tree_max_depth_shared() {
+		let options = TreeOptions {
+			max_depth: Some(2),
+			prefix_style: PrefixStyle::Ascii,
+			..TreeOptions::default()
+		};
+		assert_eq!(
+			fixture().render_tree(&options),
+			"\
+app v1.0.0
+|-- lib v1.0.0
+|   `-- util v1.0.0
+|-- util v1.0.0
+|   `-- pong v1.0.0
+`-- ping v1.0.0
+    `-- pong v1.0.0
+"
+		);
+	}
+
+	#[test]
+	fn render_tree_inverted() {
+		let options = TreeOptions {
+			root: Some(PackageId {
+				repr: "util@1.0.0".to_owned(),
+			}),
+			prefix_style: PrefixStyle::Ascii,
+			invert: true,
+			..TreeOptions::default()
+		};
+		assert_eq!(
+			fixture().render_tree(&options),
+			"\
+util v1.0.0
+|-- app v1.0.0
+`-- lib v1.0.0
+    `-- app v1.0.0
+"
+		);
+	}
+}
@@ -0,0 +1,126 @@
+//! This module contains the streaming deserialization used by [`MetadataCommand::exec_visit`].
+
+use std::fmt;
+use std::str::from_utf8;
+
+use serde::de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+
+use crate::{MetadataCommand, Package, Result, json_start};
+
+impl MetadataCommand {
+	/// Runs configured `wesl metadata` and calls `visitor` with each package.
+	///
+	/// The output of `wesl metadata` is read in full first, like for [`Self::exec`]. Packages
+	/// are then deserialized one at a time and handed to `visitor` without being collected,
+	/// so no [`Metadata`](crate::Metadata) holding every package is built next to the output.
+	/// All other fields of the metadata are skipped.
+	pub fn exec_visit<Visit: FnMut(Package)>(
+		&self,
+		visitor: Visit,
+	) -> Result<()> {
+		visit_stdout(from_utf8(&self.run()?.stdout)?, visitor)
+	}
+}
+
+/// Calls `visitor` with each package in the JSON in the stdout of `wesl metadata`.
+fn visit_stdout<Visit: FnMut(Package)>(
+	stdout: &str,
+	mut visitor: Visit,
+) -> Result<()> {
+	let mut deserializer = serde_json::Deserializer::from_str(json_start(stdout)?);
+	deserializer.deserialize_map(MetadataVisitor(&mut visitor))?;
+	Ok(())
+}
+
+/// Visits the top-level metadata object, streaming its `packages` field.
+struct MetadataVisitor<'visit, Visit>(&'visit mut Visit);
+
+impl<'de, Visit: FnMut(Package)> Visitor<'de> for MetadataVisitor<'_, Visit> {
+	type Value = ();
+
+	fn expecting(
+		&self,
+		formatter: &mut fmt::Formatter<'_>,
+	) -> fmt::Result {
+		formatter.write_str("a `wesl metadata` object")
+	}
+
+	fn visit_map<Map: MapAccess<'de>>(
+		self,
+		mut map: Map,
+	) -> Result<Self::Value, Map::Error> {
+		while let Some(key) = map.next_key::<String>()? {
+			if key == "packages" {
+				map.next_value_seed(PackagesVisitor(&mut *self.0))?;
+			} else {
+				map.next_value::<IgnoredAny>()?;
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Visits the `packages` array, handing over one package at a time.
+struct PackagesVisitor<'visit, Visit>(&'visit mut Visit);
+
+impl<'de, Visit: FnMut(Package)> DeserializeSeed<'de> for PackagesVisitor<'_, Visit> {
+	type Value = ();
+
+	fn deserialize<De: Deserializer<'de>>(
+		self,
+		deserializer: De,
+	) -> Result<Self::Value, De::Error> {
+		deserializer.deserialize_seq(self)
+	}
+}
+
+impl<'de, Visit: FnMut(Package)> Visitor<'de> for PackagesVisitor<'_, Visit> {
+	type Value = ();
+
+	fn expecting(
+		&self,
+		formatter: &mut fmt::Formatter<'_>,
+	) -> fmt::Result {
+		formatter.write_str("a list of packages")
+	}
+
+	fn visit_seq<Seq: SeqAccess<'de>>(
+		self,
+		mut seq: Seq,
+	) -> Result<Self::Value, Seq::Error> {
+		while let Some(package) = seq.next_element::<Package>()? {
+			(self.0)(package);
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tests::{metadata, package};
+
+	#[test]
+	fn visit_stdout() {
+		let metadata = metadata(
+			(0..50)
+				.map(|index| package(&format!("package-{index}"), "1.0.0"))
+				.collect(),
+		);
+		let stdout = format!(
+			"Resolving dependencies\n{}\n",
+			serde_json::to_string_pretty(&metadata).unwrap()
+		);
+		let mut count = 0;
+		let mut names = Vec::new();
+		super::visit_stdout(&stdout, |package| {
+			count += 1;
+			if names.len() < 2 {
+				names.push(package.name);
+			}
+		})
+		.unwrap();
+		assert_eq!(count, 50);
+		assert_eq!(names, ["package-0", "package-1"]);
+	}
+}
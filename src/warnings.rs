@@ -0,0 +1,145 @@
+//! This module contains [`Warning`] and [`MetadataCommand::exec_with_warnings`].
+
+use std::str::from_utf8;
+
+use camino::Utf8PathBuf;
+use serde::{Deserialize, Serialize};
+
+use crate::{Metadata, MetadataCommand, Result};
+
+/// A non-fatal diagnostic printed by `wesl metadata` to stderr.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Warning {
+	/// The text of the warning, without the `warning:` prefix.
+	pub message: String,
+	/// The manifest the warning is about, if it mentions one.
+	pub manifest_path: Option<Utf8PathBuf>,
+}
+
+impl Warning {
+	/// Parse the warnings in the stderr of `wesl metadata`.
+	///
+	/// A warning starts at a line beginning with `warning:`. The manifest path is taken from a
+	/// `wesl.toml` path in that line, or from a following `--> path:line:column` line.
+	/// Other lines are ignored.
+	#[must_use]
+	pub fn parse_stderr(stderr: &str) -> Vec<Self> {
+		let mut warnings: Vec<Self> = Vec::new();
+		for line in stderr.lines().map(str::trim) {
+			if let Some(message) = strip_prefix_ignore_case(line, "warning:") {
+				let message = message.trim();
+				warnings.push(Self {
+					message: message.to_owned(),
+					manifest_path: message.split_whitespace().find_map(manifest_path),
+				});
+			} else if let Some(location) = line.strip_prefix("-->")
+				&& let Some(warning) = warnings.last_mut()
+				&& warning.manifest_path.is_none()
+			{
+				warning.manifest_path = manifest_path(location.trim());
+			}
+		}
+		warnings
+	}
+}
+
+/// Strip `prefix` from the start of `line`, ignoring ASCII case.
+fn strip_prefix_ignore_case<'line>(
+	line: &'line str,
+	prefix: &str,
+) -> Option<&'line str> {
+	let head = line.get(..prefix.len())?;
+	head.eq_ignore_ascii_case(prefix)
+		.then(|| line.get(prefix.len()..))
+		.flatten()
+}
+
+/// The manifest path in `word`, with surrounding quotes and a `:line:column` suffix removed.
+fn manifest_path(word: &str) -> Option<Utf8PathBuf> {
+	let word = word
+		.trim_matches(|character: char| matches!(character, '`' | '\'' | '"' | ',' | '(' | ')'));
+	let end = word.find("wesl.toml")? + "wesl.toml".len();
+	Some(word.get(..end)?.into())
+}
+
+impl MetadataCommand {
+	/// Runs configured `wesl metadata` and returns parsed `Metadata` along with the warnings
+	/// printed to stderr.
+	///
+	/// See [`Warning::parse_stderr`] for how warnings are recognized.
+	pub fn exec_with_warnings(&self) -> Result<(Metadata, Vec<Warning>)> {
+		let output = self.run()?;
+		let metadata = self.parse_output(from_utf8(&output.stdout)?)?;
+		Ok((metadata, Warning::parse_stderr(from_utf8(&output.stderr)?)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	#[cfg(unix)]
+	use crate::tests::{TempDir, metadata, package};
+
+	#[test]
+	fn parse_stderr() {
+		let stderr = "\
+Resolving dependencies
+warning: unused manifest key `package.colour` in `/app/wesl.toml`
+WARNING: field `authors` is deprecated
+  --> /lib/wesl.toml:3:1
+warning: no manifest mentioned
+";
+		assert_eq!(
+			Warning::parse_stderr(stderr),
+			[
+				Warning {
+					message: "unused manifest key `package.colour` in `/app/wesl.toml`".to_owned(),
+					manifest_path: Some("/app/wesl.toml".into()),
+				},
+				Warning {
+					message: "field `authors` is deprecated".to_owned(),
+					manifest_path: Some("/lib/wesl.toml".into()),
+				},
+				Warning {
+					message: "no manifest mentioned".to_owned(),
+					manifest_path: None,
+				},
+			]
+		);
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn exec_with_warnings() {
+		let metadata = metadata(vec![package("app", "1.0.0")]);
+		let directory = TempDir::new("warnings");
+		let json = directory.write("warnings.json", &serde_json::to_string(&metadata).unwrap());
+		let wesl = directory.shim(
+			"warnings",
+			&format!(
+				"echo 'warning: unused manifest key `colour` in /app/wesl.toml' >&2\n\
+				 echo 'warning: field `authors` is deprecated' >&2\n\
+				 cat '{json}'"
+			),
+		);
+		let (parsed, warnings) = MetadataCommand::new()
+			.wesl_path(wesl)
+			.exec_with_warnings()
+			.unwrap();
+		assert_eq!(parsed, metadata);
+		assert_eq!(
+			warnings,
+			[
+				Warning {
+					message: "unused manifest key `colour` in /app/wesl.toml".to_owned(),
+					manifest_path: Some("/app/wesl.toml".into()),
+				},
+				Warning {
+					message: "field `authors` is deprecated".to_owned(),
+					manifest_path: None,
+				},
+			]
+		);
+	}
+}